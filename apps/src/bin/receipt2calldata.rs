@@ -0,0 +1,107 @@
+//! Converts a receipt written with `prove --out` into the JSON inputs of an on-chain verifier
+//! call: the seal, image ID, post-state digest and journal, along with the decoded journal
+//! fields for Solidity tooling to check against.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use apps::{eth, journal, receipt_file};
+use clap::Parser;
+use methods::{
+    BITCOIN_BLOCK_VERIFY_ID, BITCOIN_FORK_COMPARE_ID, BITCOIN_SPARSE_VERIFY_ID,
+    BITCOIN_STREAM_VERIFY_ID,
+};
+use risc0_zkvm::sha::{Digestible, Impl, Sha256};
+use serde_json::json;
+
+#[derive(Parser)]
+struct Args {
+    /// Path to a receipt written with `prove --out`.
+    receipt: PathBuf,
+
+    /// Guest the receipt was proven with, as listed by `image-ids`.
+    #[arg(long, default_value = "bitcoin-block-verify")]
+    guest: String,
+
+    /// Verify against this image ID, as the 0x-prefixed bytes32 `image-ids` prints, instead of
+    /// the one embedded for `--guest`.
+    #[arg(long, value_parser = parse_bytes32)]
+    image_id: Option<[u8; 32]>,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let bytes = fs::read(&args.receipt)
+        .with_context(|| format!("failed to read {}", args.receipt.display()))?;
+    let receipt = receipt_file::decode(&bytes)
+        .with_context(|| format!("failed to load {}", args.receipt.display()))?;
+    let image_id = match args.image_id {
+        Some(bytes) => eth::bytes32_to_image_id(bytes),
+        None => guest_image_id(&args.guest)?,
+    };
+    receipt
+        .verify(image_id)
+        .context("receipt does not verify against the image ID")?;
+
+    // Only the block-verify guest's journal has a decoder; other journals are passed through.
+    let fields = if image_id == BITCOIN_BLOCK_VERIFY_ID {
+        serde_json::to_value(journal::Journal::decode(&receipt.journal.bytes)?)?
+    } else {
+        serde_json::Value::Null
+    };
+    let output = json!({
+        "seal": format!("0x{}", hex::encode(eth::extract_seal(&receipt)?)),
+        "imageId": format!("0x{}", hex::encode(eth::image_id_to_bytes32(image_id))),
+        "postStateDigest": format!("0x{}", receipt.get_claim()?.post.digest()),
+        "journal": format!("0x{}", hex::encode(&receipt.journal.bytes)),
+        "journalDigest": format!("0x{}", Impl::hash_bytes(&receipt.journal.bytes)),
+        "fields": fields,
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Returns the image ID embedded for the guest named `guest`.
+fn guest_image_id(guest: &str) -> Result<[u32; 8]> {
+    Ok(match guest {
+        "bitcoin-block-verify" => BITCOIN_BLOCK_VERIFY_ID,
+        "bitcoin-fork-compare" => BITCOIN_FORK_COMPARE_ID,
+        "bitcoin-sparse-verify" => BITCOIN_SPARSE_VERIFY_ID,
+        "bitcoin-stream-verify" => BITCOIN_STREAM_VERIFY_ID,
+        _ => bail!("unknown guest {}", guest),
+    })
+}
+
+fn parse_bytes32(s: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).context("malformed hex")?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| anyhow::anyhow!("{} bytes, expected 32", bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use apps::eth;
+    use methods::BITCOIN_FORK_COMPARE_ID;
+
+    use super::{guest_image_id, parse_bytes32};
+
+    #[test]
+    fn resolves_image_ids() {
+        assert_eq!(
+            guest_image_id("bitcoin-fork-compare").unwrap(),
+            BITCOIN_FORK_COMPARE_ID
+        );
+        assert!(guest_image_id("is-even").is_err());
+
+        let bytes32 = format!(
+            "0x{}",
+            hex::encode(eth::image_id_to_bytes32(BITCOIN_FORK_COMPARE_ID))
+        );
+        let bytes = parse_bytes32(&bytes32).unwrap();
+        assert_eq!(eth::bytes32_to_image_id(bytes), BITCOIN_FORK_COMPARE_ID);
+        assert!(parse_bytes32("0x0102").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Splitting a long range of headers into spans proven one receipt each.
+
+use anyhow::{ensure, Result};
+use apps::headers::{HEADER_LEN, RETARGET_INTERVAL};
+
+/// Splits `start..=end` into consecutive spans of at most `max` headers.
+///
+/// Wherever a span other than the last would cross a retarget boundary, it is cut short just
+/// before the last boundary it covers, so that the next span starts on a boundary and its
+/// difficulty anchor is its own first header.
+pub fn split(start: u64, end: u64, max: u64) -> Result<Vec<(u64, u64)>> {
+    ensure!(max > 0, "--max-headers-per-proof must be positive");
+    let mut spans = Vec::new();
+    let mut span_start = start;
+    while span_start <= end {
+        let mut span_end = end.min(span_start.saturating_add(max - 1));
+        let last_boundary = span_end - span_end % RETARGET_INTERVAL;
+        if last_boundary > span_start && span_end < end {
+            span_end = last_boundary - 1;
+        }
+        spans.push((span_start, span_end));
+        if span_end == end {
+            break;
+        }
+        span_start = span_end + 1;
+    }
+    Ok(spans)
+}
+
+/// Returns the `nBits` and timestamp of the first header of the retarget window containing
+/// `height`, if that header is in `headers`, a buffer starting at `start_height`.
+pub fn window_start(headers: &[u8], start_height: u64, height: u64) -> Option<(u32, u32)> {
+    let window_start = height - height % RETARGET_INTERVAL;
+    let offset = (window_start.checked_sub(start_height)? as usize) * HEADER_LEN;
+    let header = headers.get(offset..offset + HEADER_LEN)?;
+    Some((
+        u32::from_le_bytes(header[72..76].try_into().unwrap()),
+        u32::from_le_bytes(header[68..72].try_into().unwrap()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use apps::headers::HEADER_LEN;
+
+    use super::{split, window_start};
+
+    #[test]
+    fn splits_on_retarget_boundaries() {
+        assert_eq!(split(10, 15, 100).unwrap(), vec![(10, 15)]);
+        assert_eq!(split(0, 4999, 3000).unwrap(), vec![(0, 2015), (2016, 4999)]);
+        assert_eq!(
+            split(2000, 2100, 50).unwrap(),
+            vec![(2000, 2015), (2016, 2065), (2066, 2100)]
+        );
+        assert!(split(10, 15, 0).is_err());
+    }
+
+    #[test]
+    fn splits_with_huge_max() {
+        assert_eq!(split(10, 15, u64::MAX).unwrap(), vec![(10, 15)]);
+        assert_eq!(
+            split(u64::MAX - 1, u64::MAX, u64::MAX).unwrap(),
+            vec![(u64::MAX - 1, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn finds_window_start_in_buffer() {
+        let mut headers = vec![0; 3 * HEADER_LEN];
+        let second = &mut headers[HEADER_LEN..2 * HEADER_LEN];
+        second[68..72].copy_from_slice(&1_231_006_505u32.to_le_bytes());
+        second[72..76].copy_from_slice(&0x1d00ffffu32.to_le_bytes());
+
+        assert_eq!(
+            window_start(&headers, 2015, 2017),
+            Some((0x1d00ffff, 1_231_006_505))
+        );
+        assert_eq!(window_start(&headers, 2015, 2015), None);
+    }
+}
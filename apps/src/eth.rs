@@ -0,0 +1,123 @@
+//! Helpers for submitting receipts to the on-chain RISC Zero verifier.
+
+use alloy_primitives::B256;
+use alloy_sol_types::{sol, SolCall};
+use anyhow::{bail, Context, Result};
+use risc0_ethereum_contracts::groth16;
+use risc0_zkvm::{
+    sha::{Digest, Digestible, Impl, Sha256},
+    InnerReceipt, Receipt,
+};
+
+sol! {
+    interface IRiscZeroVerifier {
+        function verify(
+            bytes calldata seal,
+            bytes32 imageId,
+            bytes32 postStateDigest,
+            bytes32 journalDigest
+        ) external view returns (bool);
+    }
+}
+
+/// Returns an image ID as the `bytes32` the verifier contract expects: each word in little-endian
+/// byte order, the same bytes as the image ID's `Digest`.
+pub fn image_id_to_bytes32(image_id: [u32; 8]) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(image_id) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+/// The inverse of [`image_id_to_bytes32`].
+pub fn bytes32_to_image_id(bytes: [u8; 32]) -> [u32; 8] {
+    let mut image_id = [0; 8];
+    for (word, chunk) in image_id.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    image_id
+}
+
+/// Returns the ABI-encoded seal the on-chain verifier takes for `receipt`.
+///
+/// Only compact (Groth16) receipts can be checked on-chain; succinct receipts have to be
+/// compressed first and fake receipts are rejected.
+pub fn extract_seal(receipt: &Receipt) -> Result<Vec<u8>> {
+    match &receipt.inner {
+        InnerReceipt::Compact(receipt) => groth16::Seal::abi_encode(receipt.seal.clone())
+            .context("failed to ABI-encode the Groth16 seal"),
+        InnerReceipt::Succinct(_) => {
+            bail!("succinct receipts cannot be verified on-chain, compress to a compact receipt first")
+        }
+        _ => bail!("only compact receipts can be verified on-chain"),
+    }
+}
+
+/// Builds the calldata for `IRiscZeroVerifier.verify` proving `receipt` against `image_id`.
+pub fn to_eth_calldata(receipt: &Receipt, image_id: [u32; 8]) -> Result<Vec<u8>> {
+    let post_state_digest = receipt.get_claim()?.post.digest();
+
+    Ok(encode_verify_call(
+        extract_seal(receipt)?,
+        image_id,
+        post_state_digest,
+        &receipt.journal.bytes,
+    ))
+}
+
+fn encode_verify_call(
+    seal: Vec<u8>,
+    image_id: [u32; 8],
+    post_state_digest: Digest,
+    journal: &[u8],
+) -> Vec<u8> {
+    IRiscZeroVerifier::verifyCall {
+        seal: seal.into(),
+        imageId: B256::from(image_id_to_bytes32(image_id)),
+        postStateDigest: B256::from_slice(post_state_digest.as_bytes()),
+        journalDigest: B256::from_slice(Impl::hash_bytes(journal).as_bytes()),
+    }
+    .abi_encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::sha::Digest;
+
+    use super::{bytes32_to_image_id, encode_verify_call, image_id_to_bytes32};
+
+    #[test]
+    fn converts_image_id_to_bytes32() {
+        let image_id = [0x0403_0201, 0x0807_0605, 0, 0, 0, 0, 0, 0xdead_beef];
+        let bytes = image_id_to_bytes32(image_id);
+
+        assert_eq!(bytes[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(bytes[28..], [0xef, 0xbe, 0xad, 0xde]);
+        assert_eq!(bytes, Digest::from(image_id).as_bytes());
+        assert_eq!(bytes32_to_image_id(bytes), image_id);
+    }
+
+    #[test]
+    fn encodes_verify_call() {
+        let calldata = encode_verify_call(
+            vec![1, 2, 3],
+            [1, 2, 3, 4, 5, 6, 7, 8],
+            Digest::from([9; 8]),
+            b"journal",
+        );
+
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "6efef009",
+                "0000000000000000000000000000000000000000000000000000000000000080",
+                "0100000002000000030000000400000005000000060000000700000008000000",
+                "0900000009000000090000000900000009000000090000000900000009000000",
+                "81dd6b775afcccb6dbb8a25a58ea844271bbefaeea7cb1d91c1687d7450f850c",
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "0102030000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+}
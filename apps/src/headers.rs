@@ -0,0 +1,181 @@
+//! Helpers for working with buffers of serialized block headers.
+
+use std::io::Read;
+
+use anyhow::{ensure, Context, Result};
+use std::fmt::Write;
+
+use bitcoin::{bip152::HeaderAndShortIds, block::Header, consensus};
+
+pub use validation::{HEADER_LEN, RETARGET_INTERVAL};
+
+/// Reads a buffer of concatenated 80-byte headers, rejecting any trailing partial header.
+pub fn read_headers(mut reader: impl Read) -> Result<Vec<u8>> {
+    let mut headers = Vec::new();
+    reader
+        .read_to_end(&mut headers)
+        .context("failed to read header buffer")?;
+    check_whole_headers(&headers)?;
+    Ok(headers)
+}
+
+/// Decodes concatenated 80-byte headers given as hex, as Electrum servers return them from
+/// `blockchain.block.headers`. Surrounding whitespace is ignored.
+pub fn headers_from_hex(hex: &str) -> Result<Vec<u8>> {
+    let headers = hex::decode(hex.trim()).context("malformed header hex")?;
+    check_whole_headers(&headers)?;
+    Ok(headers)
+}
+
+fn check_whole_headers(headers: &[u8]) -> Result<()> {
+    ensure!(
+        headers.len() % HEADER_LEN == 0,
+        "header buffer length {} is not a multiple of {}",
+        headers.len(),
+        HEADER_LEN
+    );
+    Ok(())
+}
+
+/// Returns the header at the start of `data`, such as a serialized block, or an error if `data`
+/// is too short to hold one.
+pub fn leading_header(data: &[u8]) -> Result<&[u8]> {
+    data.get(..HEADER_LEN).with_context(|| {
+        format!(
+            "{} bytes are too few for an {}-byte header",
+            data.len(),
+            HEADER_LEN
+        )
+    })
+}
+
+/// Returns the `prev_blockhash` field of a serialized header, in header digest byte order.
+pub fn prev_blockhash(header: &[u8]) -> Result<[u8; 32]> {
+    ensure!(
+        header.len() == HEADER_LEN,
+        "a header is {} bytes, not {}",
+        HEADER_LEN,
+        header.len()
+    );
+    Ok(header[4..36].try_into().unwrap())
+}
+
+/// Extracts the 80-byte header from the payload of a BIP152 `cmpctblock` message.
+pub fn header_from_compact_block(bytes: &[u8]) -> Result<[u8; HEADER_LEN]> {
+    let block: HeaderAndShortIds =
+        consensus::deserialize(bytes).context("malformed compact block")?;
+    let header = consensus::serialize(&block.header);
+    Ok(header
+        .try_into()
+        .expect("a serialized header is 80 bytes long"))
+}
+
+/// Renders the header at `height` as its raw bytes, one labeled field per line, with each
+/// field's decoded value alongside.
+pub fn describe_header(height: u64, header: &[u8]) -> Result<String> {
+    let decoded: Header = consensus::deserialize(header).context("malformed header")?;
+    let mut out = format!("header {} {}\n", height, decoded.block_hash());
+    let fields = [
+        (
+            "version",
+            0..4,
+            format!("{:#010x}", decoded.version.to_consensus()),
+        ),
+        ("prev", 4..36, decoded.prev_blockhash.to_string()),
+        ("merkle", 36..68, decoded.merkle_root.to_string()),
+        ("time", 68..72, decoded.time.to_string()),
+        (
+            "bits",
+            72..76,
+            format!("{:#010x}", decoded.bits.to_consensus()),
+        ),
+        ("nonce", 76..80, decoded.nonce.to_string()),
+    ];
+    for (label, range, value) in fields {
+        writeln!(
+            out,
+            "  {:<8}{}  {}",
+            label,
+            hex::encode(&header[range]),
+            value
+        )?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use validation::fixtures::MAINNET_HEADERS;
+
+    use super::{
+        describe_header, header_from_compact_block, headers_from_hex, leading_header,
+        prev_blockhash, read_headers, HEADER_LEN,
+    };
+
+    const GENESIS_HEADER: &str = MAINNET_HEADERS[0];
+
+    #[test]
+    fn reads_whole_headers() {
+        let buf = vec![0u8; 3 * HEADER_LEN];
+        assert_eq!(read_headers(buf.as_slice()).unwrap(), buf);
+    }
+
+    #[test]
+    fn rejects_partial_header() {
+        let buf = vec![0u8; 2 * HEADER_LEN + 1];
+        assert!(read_headers(buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn decodes_header_hex() {
+        let hex = format!("{}{}\n", GENESIS_HEADER, GENESIS_HEADER);
+        let headers = headers_from_hex(&hex).unwrap();
+        assert_eq!(headers.len(), 2 * HEADER_LEN);
+        assert_eq!(headers[..HEADER_LEN], hex::decode(GENESIS_HEADER).unwrap());
+
+        assert!(headers_from_hex(&GENESIS_HEADER[..158]).is_err());
+        assert!(headers_from_hex("zz").is_err());
+    }
+
+    #[test]
+    fn slices_header_fields_totally() {
+        let header = hex::decode(GENESIS_HEADER).unwrap();
+        let mut block = header.clone();
+        block.push(1);
+        assert_eq!(leading_header(&block).unwrap(), header.as_slice());
+        assert!(leading_header(&header[..HEADER_LEN - 1]).is_err());
+
+        assert_eq!(prev_blockhash(&header).unwrap(), [0; 32]);
+        assert!(prev_blockhash(&header[..36]).is_err());
+        assert!(prev_blockhash(&block).is_err());
+    }
+
+    #[test]
+    fn describes_header_fields() {
+        let header = hex::decode(GENESIS_HEADER).unwrap();
+        let description = describe_header(0, &header).unwrap();
+
+        assert!(description.starts_with(
+            "header 0 000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f\n"
+        ));
+        assert!(description.contains("  version 01000000  0x00000001\n"));
+        assert!(description.contains("  time    29ab5f49  1231006505\n"));
+        assert!(description.contains("  bits    ffff001d  0x1d00ffff\n"));
+        assert!(description.contains("  nonce   1dac2b7c  2083236893\n"));
+    }
+
+    #[test]
+    fn extracts_header_from_compact_block() {
+        let header = hex::decode(GENESIS_HEADER).unwrap();
+        // The header, an 8-byte nonce, no short IDs and no prefilled transactions.
+        let mut block = header.clone();
+        block.extend_from_slice(&[0; 8]);
+        block.extend_from_slice(&[0, 0]);
+
+        assert_eq!(
+            header_from_compact_block(&block).unwrap().as_slice(),
+            header
+        );
+        assert!(header_from_compact_block(&block[..HEADER_LEN + 4]).is_err());
+    }
+}
@@ -0,0 +1,936 @@
+//! Decoding of the journal committed by the `bitcoin-block-verify` guest.
+
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::SolValue;
+use anyhow::{anyhow, ensure, Context, Result};
+use clap::ValueEnum;
+use risc0_zkvm::{sha::Digest, Receipt};
+use serde::Serialize;
+use validation::{Network, RetargetAnchor};
+
+use crate::headers;
+
+/// First byte of a [`JournalFormat::Compact`] journal. An ethabi journal always starts with a
+/// zero byte, since the committed height is far below 2^248.
+const COMPACT_JOURNAL_TAG: u8 = 1;
+
+/// Length of a compact journal without per-header hashes.
+const COMPACT_JOURNAL_LEN: usize =
+    1 + 8 + 32 + 1 + 4 + 4 + 4 + 1 + 8 + 32 + 8 + 32 + 4 + 4 + 4 + 4 + 8 + 4 + 4 + 1;
+
+/// Length of an ethabi journal without per-header hashes.
+const ETHABI_JOURNAL_LEN: usize = 19 * 32;
+
+/// The fixed fields of an ethabi journal, in order.
+type EthabiFields = (
+    U256,
+    B256,
+    bool,
+    U256,
+    u32,
+    u32,
+    bool,
+    U256,
+    B256,
+    U256,
+    B256,
+    u32,
+    u32,
+    u32,
+    u32,
+    U256,
+    u32,
+    u32,
+    u8,
+);
+
+/// Layout of the journal committed by the guest.
+///
+/// When the hash of every header is committed, it follows the fields below in order: as a
+/// trailing `bytes32[] hashes` parameter for ethabi, or as raw 32-byte hashes for compact. Every
+/// layout ends with the id of the network the span was checked for, and the reduced ones lead
+/// with a `uint8 layout` tag holding their position in this enum, 2 to 4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum JournalFormat {
+    /// `abi.encode(uint256 height, bytes32 hash, bool checkpointed, uint256 medianTimePast,
+    /// uint32 anchorBits, uint32 anchorTime, bool powChecked, uint256 assumeValidHeight,
+    /// bytes32 assumeValidHash, uint256 startHeight, bytes32 parentHash, uint32 versionBitsMask,
+    /// uint32 versionBitsCount, uint32 endAnchorBits, uint32 endAnchorTime,
+    /// uint256 halvingHeight, uint32 firstTime, uint32 tipTime, uint8 network)`, as expected
+    /// on-chain.
+    Ethabi,
+    /// `0x01 || height (u64 LE) || hash || checkpointed (u8) || median_time_past (u32 LE) ||
+    /// anchor_bits (u32 LE) || anchor_time (u32 LE) || pow_checked (u8) ||
+    /// assume_valid_height (u64 LE) || assume_valid_hash || start_height (u64 LE) ||
+    /// parent_hash || version_bits_mask (u32 LE) || version_bits_count (u32 LE) ||
+    /// end_anchor_bits (u32 LE) || end_anchor_time (u32 LE) || halving_height (u64 LE) ||
+    /// first_time (u32 LE) || tip_time (u32 LE) || network (u8)`, for off-chain consumers.
+    Compact,
+    /// `abi.encode(uint8 layout, uint256 height, bytes32 hash, bool checkpointed,
+    /// uint32 anchorBits, uint32 anchorTime, uint8 network)`, for contracts that only track the
+    /// tip.
+    HeightHash,
+    /// `abi.encode(uint8 layout, uint256 startHeight, uint256 height, bytes32 hash,
+    /// bool checkpointed, uint32 anchorBits, uint32 anchorTime, uint8 network)`.
+    StartHeightHash,
+    /// `abi.encode(uint8 layout, uint256 height, bytes32 hash, bool checkpointed,
+    /// uint256 medianTimePast, uint32 anchorBits, uint32 anchorTime, uint8 network)`.
+    HeightHashTime,
+}
+
+impl JournalFormat {
+    /// Whether the layout is one of the reduced ethabi tuples, which match an existing contract's
+    /// journal struct but leave out the fields qualifying how proof of work was checked. The
+    /// guest only commits them for a span whose proof of work was checked in full, without
+    /// `--assume-valid`, `--require-version-bits` or committed hashes. The anchor and checkpoint
+    /// flag are kept, since the anchor is chosen by the host and must still be checked.
+    pub fn is_reduced(self) -> bool {
+        matches!(
+            self,
+            JournalFormat::HeightHash
+                | JournalFormat::StartHeightHash
+                | JournalFormat::HeightHashTime
+        )
+    }
+
+    /// Tag a reduced journal leads with.
+    fn tag(self) -> u8 {
+        self as u8
+    }
+}
+
+/// The values committed by the guest.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Journal {
+    pub format: JournalFormat,
+    /// Height of the tip, the last proven header.
+    pub height: u64,
+    /// Hash of the last proven header, in header digest byte order.
+    pub hash: B256,
+    /// Whether any mainnet checkpoint fell within the proven span.
+    pub checkpointed: bool,
+    /// Median timestamp of the tip and the ten blocks before it, or zero if the span was too
+    /// short to tell.
+    pub median_time_past: u32,
+    /// `nBits` the guest trusted for the retarget window containing the first proven header.
+    pub anchor_bits: u32,
+    /// Timestamp the guest trusted for the first header of that window.
+    pub anchor_time: u32,
+    /// Whether the guest checked proof of work. Only regtest spans may be proven without it,
+    /// and such a journal attests to linkage alone.
+    pub pow_checked: bool,
+    /// Height and hash of the `--assume-valid` block, if it was in the span. Proof of work was
+    /// only checked for the headers after it.
+    pub assume_valid: Option<(u64, B256)>,
+    /// Height of the first proven header.
+    pub start_height: u64,
+    /// `prev_blockhash` of the first proven header, the tip the span extends.
+    pub parent_hash: B256,
+    /// The `--require-version-bits` mask and how many headers of the span signaled it, if a
+    /// signaling threshold was required.
+    pub version_bits: Option<(u32, u32)>,
+    /// `nBits` in force for the header after the tip, retargeted if that header starts a new
+    /// window. Zero if proof of work was not checked.
+    pub end_anchor_bits: u32,
+    /// Timestamp of the first header of the window containing the header after the tip, or zero
+    /// if that header starts a new window or proof of work was not checked.
+    pub end_anchor_time: u32,
+    /// Height of the subsidy halving within the span, if `--detect-halving` was given and the
+    /// span contains one.
+    pub halving_height: Option<u64>,
+    /// Timestamp of the first proven header. Zero for a reduced layout, which leaves it out.
+    pub first_time: u32,
+    /// Timestamp of the tip. Zero for a reduced layout, which leaves it out.
+    pub tip_time: u32,
+    /// Network the span's proof of work and difficulty were checked for.
+    pub network: Network,
+    /// Hash of every proven header in order, if the guest was asked to commit them.
+    pub hashes: Vec<B256>,
+}
+
+impl Journal {
+    /// Decodes a journal in any layout. A compact journal leads with its tag byte, and a reduced
+    /// one is shorter than the full ethabi layout and leads with its tag word.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.first() == Some(&COMPACT_JOURNAL_TAG) {
+            ensure!(
+                bytes.len() >= COMPACT_JOURNAL_LEN && (bytes.len() - COMPACT_JOURNAL_LEN) % 32 == 0,
+                "compact journal has length {}",
+                bytes.len()
+            );
+            return Ok(Self {
+                format: JournalFormat::Compact,
+                height: u64::from_le_bytes(bytes[1..9].try_into()?),
+                hash: B256::from_slice(&bytes[9..41]),
+                checkpointed: bytes[41] != 0,
+                median_time_past: u32::from_le_bytes(bytes[42..46].try_into()?),
+                anchor_bits: u32::from_le_bytes(bytes[46..50].try_into()?),
+                anchor_time: u32::from_le_bytes(bytes[50..54].try_into()?),
+                pow_checked: bytes[54] != 0,
+                assume_valid: assume_valid(
+                    u64::from_le_bytes(bytes[55..63].try_into()?),
+                    B256::from_slice(&bytes[63..95]),
+                ),
+                start_height: u64::from_le_bytes(bytes[95..103].try_into()?),
+                parent_hash: B256::from_slice(&bytes[103..135]),
+                version_bits: version_bits(
+                    u32::from_le_bytes(bytes[135..139].try_into()?),
+                    u32::from_le_bytes(bytes[139..143].try_into()?),
+                ),
+                end_anchor_bits: u32::from_le_bytes(bytes[143..147].try_into()?),
+                end_anchor_time: u32::from_le_bytes(bytes[147..151].try_into()?),
+                halving_height: halving_height(u64::from_le_bytes(bytes[151..159].try_into()?)),
+                first_time: u32::from_le_bytes(bytes[159..163].try_into()?),
+                tip_time: u32::from_le_bytes(bytes[163..167].try_into()?),
+                network: network(bytes[167])?,
+                hashes: bytes[COMPACT_JOURNAL_LEN..]
+                    .chunks_exact(32)
+                    .map(B256::from_slice)
+                    .collect(),
+            });
+        }
+
+        if bytes.len() < ETHABI_JOURNAL_LEN {
+            return Self::decode_reduced(bytes);
+        }
+        // A static tuple is encoded inline, so the fields decode from the same bytes whether
+        // or not the hashes follow them.
+        let (fields, hashes) = if bytes.len() == ETHABI_JOURNAL_LEN {
+            (EthabiFields::abi_decode(bytes, true)?, Vec::new())
+        } else {
+            <(EthabiFields, Vec<B256>)>::abi_decode_params(bytes, true)?
+        };
+        let (
+            height,
+            hash,
+            checkpointed,
+            median_time_past,
+            anchor_bits,
+            anchor_time,
+            pow_checked,
+            assume_valid_height,
+            assume_valid_hash,
+            start_height,
+            parent_hash,
+            version_bits_mask,
+            version_bits_count,
+            end_anchor_bits,
+            end_anchor_time,
+            halving,
+            first_time,
+            tip_time,
+            network_id,
+        ) = fields;
+        Ok(Self {
+            format: JournalFormat::Ethabi,
+            height: narrow(height, "height")?,
+            hash,
+            checkpointed,
+            median_time_past: narrow(median_time_past, "median time past")?,
+            anchor_bits,
+            anchor_time,
+            pow_checked,
+            assume_valid: assume_valid(
+                narrow(assume_valid_height, "assume-valid height")?,
+                assume_valid_hash,
+            ),
+            start_height: narrow(start_height, "start height")?,
+            parent_hash,
+            version_bits: version_bits(version_bits_mask, version_bits_count),
+            end_anchor_bits,
+            end_anchor_time,
+            halving_height: halving_height(narrow(halving, "halving height")?),
+            first_time,
+            tip_time,
+            network: network(network_id)?,
+            hashes,
+        })
+    }
+
+    /// Decodes a journal known to be in `format`, failing if it is in another.
+    pub fn decode_as(bytes: &[u8], format: JournalFormat) -> Result<Self> {
+        let journal = Self::decode(bytes)?;
+        ensure!(
+            journal.format == format,
+            "journal is {:?}, not {:?}",
+            journal.format,
+            format
+        );
+        Ok(journal)
+    }
+
+    /// Decodes a journal in one of the reduced layouts, telling them apart by the leading tag
+    /// word. The fields a reduced layout leaves out are set to what the guest requires of a span
+    /// it commits that way.
+    fn decode_reduced(bytes: &[u8]) -> Result<Self> {
+        ensure!(bytes.len() >= 32, "journal has length {}", bytes.len());
+        let tag = U256::abi_decode(&bytes[..32], true)?;
+        let format = [
+            JournalFormat::HeightHash,
+            JournalFormat::StartHeightHash,
+            JournalFormat::HeightHashTime,
+        ]
+        .into_iter()
+        .find(|format| U256::from(format.tag()) == tag)
+        .with_context(|| {
+            format!(
+                "journal of {} bytes has unknown layout tag {}",
+                bytes.len(),
+                tag
+            )
+        })?;
+        let words = if format == JournalFormat::HeightHash {
+            7
+        } else {
+            8
+        };
+        ensure!(
+            bytes.len() == words * 32,
+            "{:?} journal has length {}",
+            format,
+            bytes.len()
+        );
+        let (
+            height,
+            hash,
+            checkpointed,
+            anchor_bits,
+            anchor_time,
+            network_id,
+            start_height,
+            median_time_past,
+        ) = match format {
+            JournalFormat::StartHeightHash => {
+                let (
+                    _,
+                    start_height,
+                    height,
+                    hash,
+                    checkpointed,
+                    anchor_bits,
+                    anchor_time,
+                    network,
+                ) = <(u8, U256, U256, B256, bool, u32, u32, u8)>::abi_decode(bytes, true)?;
+                (
+                    height,
+                    hash,
+                    checkpointed,
+                    anchor_bits,
+                    anchor_time,
+                    network,
+                    start_height,
+                    U256::ZERO,
+                )
+            }
+            JournalFormat::HeightHashTime => {
+                let (
+                    _,
+                    height,
+                    hash,
+                    checkpointed,
+                    median_time_past,
+                    anchor_bits,
+                    anchor_time,
+                    network,
+                ) = <(u8, U256, B256, bool, U256, u32, u32, u8)>::abi_decode(bytes, true)?;
+                (
+                    height,
+                    hash,
+                    checkpointed,
+                    anchor_bits,
+                    anchor_time,
+                    network,
+                    U256::ZERO,
+                    median_time_past,
+                )
+            }
+            _ => {
+                let (_, height, hash, checkpointed, anchor_bits, anchor_time, network) =
+                    <(u8, U256, B256, bool, u32, u32, u8)>::abi_decode(bytes, true)?;
+                (
+                    height,
+                    hash,
+                    checkpointed,
+                    anchor_bits,
+                    anchor_time,
+                    network,
+                    U256::ZERO,
+                    U256::ZERO,
+                )
+            }
+        };
+        Ok(Self {
+            format,
+            height: narrow(height, "height")?,
+            hash,
+            checkpointed,
+            median_time_past: narrow(median_time_past, "median time past")?,
+            anchor_bits,
+            anchor_time,
+            pow_checked: true,
+            assume_valid: None,
+            start_height: narrow(start_height, "start height")?,
+            parent_hash: B256::ZERO,
+            version_bits: None,
+            end_anchor_bits: 0,
+            end_anchor_time: 0,
+            halving_height: None,
+            first_time: 0,
+            tip_time: 0,
+            network: network(network_id)?,
+            hashes: Vec::new(),
+        })
+    }
+
+    /// The anchor a span directly extending this one must be proven with, given its first
+    /// header, or `None` if no anchor was committed: when proof of work was not checked, or the
+    /// layout is a reduced one.
+    pub fn next_anchor(&self, first_header: &[u8]) -> Result<Option<RetargetAnchor>> {
+        if !self.pow_checked || self.format.is_reduced() {
+            return Ok(None);
+        }
+        let window_start_time = match self.end_anchor_time {
+            // The span starts a new window, so its own first header starts it.
+            0 => validation::timestamp(headers::leading_header(first_header)?),
+            time => time,
+        };
+        Ok(Some(RetargetAnchor {
+            bits: self.end_anchor_bits,
+            window_start_time,
+        }))
+    }
+
+    /// Checks that a span starting at `start_height` with `first_header` directly extends the
+    /// tip this journal commits to.
+    pub fn check_extended_by(&self, start_height: u64, first_header: &[u8]) -> Result<()> {
+        ensure!(
+            self.height + 1 == start_height,
+            "previous proof ends at height {}, but the span starts at {}",
+            self.height,
+            start_height
+        );
+        ensure!(
+            headers::prev_blockhash(first_header)? == self.hash.0,
+            "the span's first header does not extend the previous proof's tip"
+        );
+        Ok(())
+    }
+}
+
+/// Narrows a committed `uint256` to the type the guest committed it from.
+fn narrow<T: TryFrom<U256>>(value: U256, name: &str) -> Result<T> {
+    T::try_from(value).map_err(|_| anyhow!("committed {} {} is out of range", name, value))
+}
+
+/// Looks up the network the guest committed the id of.
+fn network(id: u8) -> Result<Network> {
+    Network::from_id(id).ok_or_else(|| anyhow!("committed network id {} is unknown", id))
+}
+
+/// The guest commits zeros when the assume-valid block was not in the span.
+fn assume_valid(height: u64, hash: B256) -> Option<(u64, B256)> {
+    (hash != B256::ZERO).then_some((height, hash))
+}
+
+/// The guest commits a zero mask when no signaling threshold was required.
+fn version_bits(mask: u32, count: u32) -> Option<(u32, u32)> {
+    (mask != 0).then_some((mask, count))
+}
+
+/// The guest commits zero when it did not look for a halving or the span contains none, since
+/// the genesis block is not one.
+fn halving_height(height: u64) -> Option<u64> {
+    (height != 0).then_some(height)
+}
+
+/// Verifies `receipt` against `image_id` and only then decodes its journal, so the journal of a
+/// receipt that does not verify is never read.
+pub fn verify_and_decode(receipt: &Receipt, image_id: impl Into<Digest>) -> Result<Journal> {
+    receipt
+        .verify(image_id)
+        .context("receipt does not verify against the guest image ID")?;
+    Journal::decode(&receipt.journal.bytes)
+}
+
+/// Like [`verify_and_decode`], for a journal known to be in `format`.
+pub fn verify_and_decode_as(
+    receipt: &Receipt,
+    image_id: impl Into<Digest>,
+    format: JournalFormat,
+) -> Result<Journal> {
+    receipt
+        .verify(image_id)
+        .context("receipt does not verify against the guest image ID")?;
+    Journal::decode_as(&receipt.journal.bytes, format)
+}
+
+/// Describes where two journals of the same span differ, 32-byte word by word as the ethabi
+/// layout is, or returns `None` if they are identical.
+pub fn describe_divergence(a: &[u8], b: &[u8]) -> Option<String> {
+    if a == b {
+        return None;
+    }
+    let mut description = format!("journals of {} and {} bytes differ", a.len(), b.len());
+    for word in 0..a.len().max(b.len()).div_ceil(32) {
+        let range =
+            |bytes: &[u8]| &bytes[(word * 32).min(bytes.len())..(word * 32 + 32).min(bytes.len())];
+        let (a, b) = (range(a), range(b));
+        if a != b {
+            description.push_str(&format!(
+                "\n  word {} (offset {}): 0x{} != 0x{}",
+                word,
+                word * 32,
+                hex::encode(a),
+                hex::encode(b)
+            ));
+        }
+    }
+    Some(description)
+}
+
+/// Verifies both receipts and checks that `later` proves the span directly after `earlier`'s,
+/// so together they attest to one unbroken chain.
+pub fn verify_chain_extends(
+    earlier: &Receipt,
+    later: &Receipt,
+    image_id: impl Into<Digest>,
+) -> Result<()> {
+    let image_id = image_id.into();
+    let earlier = verify_and_decode(earlier, image_id).context("earlier receipt")?;
+    let later = verify_and_decode(later, image_id).context("later receipt")?;
+    ensure!(
+        later.network == earlier.network,
+        "later receipt was proven for {:?}, but the earlier one for {:?}",
+        later.network,
+        earlier.network
+    );
+    ensure!(
+        later.start_height == earlier.height + 1,
+        "later receipt starts at height {}, but the earlier one ends at {}",
+        later.start_height,
+        earlier.height
+    );
+    ensure!(
+        later.parent_hash == earlier.hash,
+        "later receipt's first header extends {}, not the earlier receipt's tip {}",
+        later.parent_hash,
+        earlier.hash
+    );
+    if earlier.pow_checked {
+        ensure!(
+            later.anchor_bits == earlier.end_anchor_bits
+                && (earlier.end_anchor_time == 0 || later.anchor_time == earlier.end_anchor_time),
+            "later receipt is anchored at {:#010x}@{}, but the earlier one ends at {:#010x}@{}",
+            later.anchor_bits,
+            later.anchor_time,
+            earlier.end_anchor_bits,
+            earlier.end_anchor_time
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use alloy_primitives::{B256, U256};
+    use alloy_sol_types::SolValue;
+    use risc0_zkvm::{sha::Digest, InnerReceipt, Receipt, ReceiptClaim, VerificationError};
+    use validation::{Network, RetargetAnchor};
+
+    use super::{
+        describe_divergence, verify_and_decode, verify_chain_extends, Journal, JournalFormat,
+    };
+
+    /// Fake receipts only verify in dev mode. Every test verifying one enables it through here,
+    /// so that none depends on another having set the process-wide variable first.
+    fn enable_dev_mode() {
+        static DEV_MODE: Once = Once::new();
+        DEV_MODE.call_once(|| std::env::set_var("RISC0_DEV_MODE", "1"));
+    }
+
+    /// A fake receipt of `image_id` committing an ethabi journal for the mainnet span
+    /// `start_height..=height` on top of `parent`, ending at `tip`.
+    fn fake_span_receipt(
+        image_id: Digest,
+        start_height: u64,
+        parent: B256,
+        height: u64,
+        tip: B256,
+    ) -> Receipt {
+        fake_network_span_receipt(
+            image_id,
+            Network::Mainnet,
+            start_height,
+            parent,
+            height,
+            tip,
+        )
+    }
+
+    /// Like [`fake_span_receipt`], for a span of `network`.
+    fn fake_network_span_receipt(
+        image_id: Digest,
+        network: Network,
+        start_height: u64,
+        parent: B256,
+        height: u64,
+        tip: B256,
+    ) -> Receipt {
+        let journal = (
+            U256::from(height),
+            tip,
+            false,
+            U256::ZERO,
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            true,
+            U256::ZERO,
+            B256::ZERO,
+            U256::from(start_height),
+            parent,
+            0u32,
+            0u32,
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            U256::ZERO,
+            1_231_006_505u32,
+            1_231_469_744u32,
+            network.id(),
+        )
+            .abi_encode();
+        let claim = ReceiptClaim::ok(image_id, journal.clone());
+        Receipt::new(InnerReceipt::Fake { claim }, journal)
+    }
+
+    /// A fake receipt of `image_id` committing an ethabi journal for heights 10 through 15.
+    fn fake_receipt(image_id: Digest) -> Receipt {
+        fake_span_receipt(
+            image_id,
+            10,
+            B256::repeat_byte(0x9a),
+            15,
+            B256::repeat_byte(0xab),
+        )
+    }
+
+    #[test]
+    fn verifies_before_decoding() {
+        enable_dev_mode();
+        let image_id = Digest::from([1; 8]);
+
+        let journal = verify_and_decode(&fake_receipt(image_id), image_id).unwrap();
+        assert_eq!(journal.height, 15);
+    }
+
+    #[test]
+    fn rejects_receipt_for_another_image() {
+        enable_dev_mode();
+        let receipt = fake_receipt(Digest::from([1; 8]));
+        // The receipt itself is sound, so only the image ID can fail it below.
+        assert!(verify_and_decode(&receipt, Digest::from([1; 8])).is_ok());
+
+        let err = verify_and_decode(&receipt, Digest::from([2; 8])).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not verify against the guest image ID"));
+        // The fake receipt's claim commits to the other image, so its digest is not the one
+        // expected for this image ID.
+        assert!(matches!(
+            err.downcast_ref::<VerificationError>(),
+            Some(VerificationError::ClaimDigestMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn decodes_both_formats() {
+        let hash = B256::repeat_byte(0xab);
+
+        let ethabi = (
+            U256::from(15),
+            hash,
+            true,
+            U256::from(1_231_469_744),
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            true,
+            U256::from(12),
+            B256::repeat_byte(0xcd),
+            U256::from(10),
+            B256::repeat_byte(0x9a),
+            1u32 << 2,
+            9u32,
+            0x1c7fff80u32,
+            0u32,
+            U256::from(210_000),
+            1_231_006_505u32,
+            1_231_469_744u32,
+            Network::Testnet.id(),
+        )
+            .abi_encode();
+        let mut compact = vec![1];
+        compact.extend_from_slice(&15u64.to_le_bytes());
+        compact.extend_from_slice(hash.as_slice());
+        compact.push(1);
+        compact.extend_from_slice(&1_231_469_744u32.to_le_bytes());
+        compact.extend_from_slice(&0x1d00ffffu32.to_le_bytes());
+        compact.extend_from_slice(&1_231_006_505u32.to_le_bytes());
+        compact.push(1);
+        compact.extend_from_slice(&12u64.to_le_bytes());
+        compact.extend_from_slice(B256::repeat_byte(0xcd).as_slice());
+        compact.extend_from_slice(&10u64.to_le_bytes());
+        compact.extend_from_slice(B256::repeat_byte(0x9a).as_slice());
+        compact.extend_from_slice(&(1u32 << 2).to_le_bytes());
+        compact.extend_from_slice(&9u32.to_le_bytes());
+        compact.extend_from_slice(&0x1c7fff80u32.to_le_bytes());
+        compact.extend_from_slice(&0u32.to_le_bytes());
+        compact.extend_from_slice(&210_000u64.to_le_bytes());
+        compact.extend_from_slice(&1_231_006_505u32.to_le_bytes());
+        compact.extend_from_slice(&1_231_469_744u32.to_le_bytes());
+        compact.push(Network::Testnet.id());
+
+        for (bytes, format) in [
+            (ethabi, JournalFormat::Ethabi),
+            (compact, JournalFormat::Compact),
+        ] {
+            assert_eq!(
+                Journal::decode(&bytes).unwrap(),
+                Journal {
+                    format,
+                    height: 15,
+                    hash,
+                    checkpointed: true,
+                    median_time_past: 1_231_469_744,
+                    anchor_bits: 0x1d00ffff,
+                    anchor_time: 1_231_006_505,
+                    pow_checked: true,
+                    assume_valid: Some((12, B256::repeat_byte(0xcd))),
+                    start_height: 10,
+                    parent_hash: B256::repeat_byte(0x9a),
+                    version_bits: Some((1 << 2, 9)),
+                    end_anchor_bits: 0x1c7fff80,
+                    end_anchor_time: 0,
+                    halving_height: Some(210_000),
+                    first_time: 1_231_006_505,
+                    tip_time: 1_231_469_744,
+                    network: Network::Testnet,
+                    hashes: Vec::new(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn describes_divergent_words() {
+        let a = [[0u8; 32], [1; 32], [2; 32]].concat();
+        assert_eq!(describe_divergence(&a, &a), None);
+
+        let mut b = a.clone();
+        b[40] = 0xff;
+        b.truncate(80);
+        let description = describe_divergence(&a, &b).unwrap();
+        assert!(description.starts_with("journals of 96 and 80 bytes differ"));
+        assert!(!description.contains("word 0"));
+        assert!(description.contains(&format!("word 1 (offset 32): 0x{}", "01".repeat(32))));
+        assert!(description.contains(&format!(
+            "word 2 (offset 64): 0x{} != 0x{}",
+            "02".repeat(32),
+            "02".repeat(16)
+        )));
+    }
+
+    #[test]
+    fn decodes_reduced_layouts() {
+        let hash = B256::repeat_byte(0xab);
+
+        let journal = Journal::decode_as(
+            &(
+                3u8,
+                U256::from(10),
+                U256::from(15),
+                hash,
+                false,
+                0x1d00ffffu32,
+                1_231_006_505u32,
+                Network::Signet.id(),
+            )
+                .abi_encode(),
+            JournalFormat::StartHeightHash,
+        )
+        .unwrap();
+        assert_eq!((journal.start_height, journal.height), (10, 15));
+        assert_eq!(journal.hash, hash);
+        assert_eq!(
+            (journal.anchor_bits, journal.anchor_time),
+            (0x1d00ffff, 1_231_006_505)
+        );
+        assert!(journal.pow_checked);
+        assert_eq!(journal.network, Network::Signet);
+
+        let bytes = (
+            4u8,
+            U256::from(15),
+            hash,
+            true,
+            U256::from(1_231_469_744),
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            Network::Mainnet.id(),
+        )
+            .abi_encode();
+        let journal = Journal::decode_as(&bytes, JournalFormat::HeightHashTime).unwrap();
+        assert_eq!(journal.median_time_past, 1_231_469_744);
+        assert!(journal.checkpointed);
+        assert_eq!(journal.format, JournalFormat::HeightHashTime);
+        assert!(Journal::decode_as(&bytes, JournalFormat::HeightHash).is_err());
+        // The tag tells the layout apart without the format.
+        assert_eq!(
+            Journal::decode(&bytes).unwrap().format,
+            JournalFormat::HeightHashTime
+        );
+        let mut mistagged = bytes.clone();
+        mistagged[31] = 5;
+        let err = Journal::decode(&mistagged).unwrap_err();
+        assert!(err.to_string().contains("unknown layout tag 5"), "{}", err);
+
+        let full = fake_receipt(Digest::ZERO).journal.bytes;
+        assert!(Journal::decode_as(&full, JournalFormat::Ethabi).is_ok());
+        assert!(Journal::decode_as(&full, JournalFormat::Compact).is_err());
+    }
+
+    #[test]
+    fn checks_extension_of_tip() {
+        // The fake receipt's journal commits tip 0xab..ab at height 15.
+        let journal = Journal::decode(&fake_receipt(Digest::ZERO).journal.bytes).unwrap();
+        let tip = B256::repeat_byte(0xab);
+        let mut header = [0; 80];
+        header[4..36].copy_from_slice(tip.as_slice());
+
+        assert!(journal.check_extended_by(16, &header).is_ok());
+        assert!(journal.check_extended_by(17, &header).is_err());
+        assert!(journal.check_extended_by(16, &[0; 80]).is_err());
+    }
+
+    #[test]
+    fn anchors_next_span() {
+        let mut journal = Journal::decode(&fake_receipt(Digest::ZERO).journal.bytes).unwrap();
+        let mut header = [0; 80];
+        header[68..72].copy_from_slice(&1_232_000_000u32.to_le_bytes());
+
+        // Mid-window, the next span shares this one's window.
+        assert_eq!(
+            journal.next_anchor(&header).unwrap(),
+            Some(RetargetAnchor {
+                bits: 0x1d00ffff,
+                window_start_time: 1_231_006_505,
+            })
+        );
+
+        // On a boundary, the next span's first header starts the window.
+        journal.end_anchor_time = 0;
+        assert_eq!(
+            journal.next_anchor(&header).unwrap(),
+            Some(RetargetAnchor {
+                bits: 0x1d00ffff,
+                window_start_time: 1_232_000_000,
+            })
+        );
+        assert!(journal.next_anchor(&header[..79]).is_err());
+
+        journal.pow_checked = false;
+        assert_eq!(journal.next_anchor(&header).unwrap(), None);
+    }
+
+    #[test]
+    fn decodes_committed_hashes() {
+        let tip = B256::repeat_byte(0xab);
+        let hashes = vec![B256::repeat_byte(0xcd), tip];
+
+        let journal = (
+            U256::from(15),
+            tip,
+            false,
+            U256::ZERO,
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            true,
+            U256::ZERO,
+            B256::ZERO,
+            U256::from(14),
+            B256::repeat_byte(0x9a),
+            0u32,
+            0u32,
+            0x1d00ffffu32,
+            1_231_006_505u32,
+            U256::ZERO,
+            1_231_006_505u32,
+            1_231_469_744u32,
+            Network::Mainnet.id(),
+            hashes.clone(),
+        )
+            .abi_encode_params();
+
+        let journal = Journal::decode(&journal).unwrap();
+        assert_eq!(journal.hashes, hashes);
+        assert_eq!(journal.assume_valid, None);
+        assert_eq!(journal.version_bits, None);
+        assert_eq!(journal.halving_height, None);
+        assert_eq!(journal.start_height, 14);
+    }
+
+    #[test]
+    fn verifies_chained_receipts() {
+        enable_dev_mode();
+        let image_id = Digest::from([1; 8]);
+        let earlier = fake_receipt(image_id);
+        let tip = B256::repeat_byte(0xab);
+        let next = B256::repeat_byte(0xef);
+
+        let later = fake_span_receipt(image_id, 16, tip, 20, next);
+        assert!(verify_chain_extends(&earlier, &later, image_id).is_ok());
+
+        let gap = fake_span_receipt(image_id, 17, tip, 20, next);
+        let err = verify_chain_extends(&earlier, &gap, image_id).unwrap_err();
+        assert!(err.to_string().contains("starts at height 17"));
+
+        let fork = fake_span_receipt(image_id, 16, next, 20, next);
+        let err = verify_chain_extends(&earlier, &fork, image_id).unwrap_err();
+        assert!(err.to_string().contains("not the earlier receipt's tip"));
+
+        let other_image = fake_span_receipt(Digest::from([2; 8]), 16, tip, 20, next);
+        assert!(verify_chain_extends(&earlier, &other_image, image_id).is_err());
+
+        let regtest = fake_network_span_receipt(image_id, Network::Regtest, 16, tip, 20, next);
+        let err = verify_chain_extends(&earlier, &regtest, image_id).unwrap_err();
+        assert!(err.to_string().contains("proven for Regtest"));
+
+        // A later span anchored at other than the target in force after the earlier one.
+        let journal = (
+            U256::from(20),
+            next,
+            false,
+            U256::ZERO,
+            0x1c7fff80u32,
+            1_231_006_505u32,
+            true,
+            U256::ZERO,
+            B256::ZERO,
+            U256::from(16),
+            tip,
+            0u32,
+            0u32,
+            0x1c7fff80u32,
+            1_231_006_505u32,
+            U256::ZERO,
+            1_231_006_505u32,
+            1_231_469_744u32,
+            Network::Mainnet.id(),
+        )
+            .abi_encode();
+        let claim = ReceiptClaim::ok(image_id, journal.clone());
+        let reanchored = Receipt::new(InnerReceipt::Fake { claim }, journal);
+        let err = verify_chain_extends(&earlier, &reanchored, image_id).unwrap_err();
+        assert!(err.to_string().contains("anchored at 0x1c7fff80"));
+    }
+}
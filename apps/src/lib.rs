@@ -0,0 +1,7 @@
+//! The modules of the host that are shared by its binaries and the fuzz targets: header parsing,
+//! journal decoding, receipt files and on-chain calldata.
+
+pub mod eth;
+pub mod headers;
+pub mod journal;
+pub mod receipt_file;
@@ -1,70 +1,1267 @@
-use std::env::set_var;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use bitcoincore_rpc::{Auth, Client, RpcApi};
-use methods::{BITCOIN_BLOCK_VERIFY_ELF, BITCOIN_BLOCK_VERIFY_ID};
-use risc0_zkvm::{default_prover, sha::Digestible, ExecutorEnv};
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::SolValue;
+use anyhow::{bail, ensure, Context, Result};
+use apps::{
+    eth,
+    headers::{self, HEADER_LEN},
+    journal::{self, Journal, JournalFormat},
+    receipt_file,
+};
+use bitcoin::{hashes::Hash, BlockHash};
+use bitcoin_spv::types::HeaderArray;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use methods::{
+    BITCOIN_BLOCK_VERIFY_ELF, BITCOIN_BLOCK_VERIFY_ID, BITCOIN_FORK_COMPARE_ELF,
+    BITCOIN_FORK_COMPARE_ID, BITCOIN_SPARSE_VERIFY_ELF, BITCOIN_SPARSE_VERIFY_ID,
+    BITCOIN_STREAM_VERIFY_ELF, BITCOIN_STREAM_VERIFY_ID,
+};
+use risc0_zkvm::{
+    default_executor, default_prover,
+    sha::{Digest, Digestible, Impl, Sha256},
+    ExecutorEnv, ExitCode, InnerReceipt, LocalProver, Prover, Receipt,
+};
+use serde::Serialize;
+use validation::{RetargetAnchor, VersionBits};
 
-fn main() {
+use crate::{
+    rest::RestArgs,
+    rpc::{RangeArgs, RpcArgs},
+    source::HeaderSource,
+    summary::ChainSummary,
+};
+
+mod chunks;
+mod rest;
+mod rpc;
+mod source;
+mod summary;
+#[cfg(test)]
+mod test_vectors;
+
+/// The Bitcoin network the proven headers belong to.
+#[derive(Clone, Copy, Debug, ValueEnum, Serialize)]
+enum Network {
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl From<Network> for validation::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Mainnet => validation::Network::Mainnet,
+            Network::Testnet => validation::Network::Testnet,
+            Network::Signet => validation::Network::Signet,
+            Network::Regtest => validation::Network::Regtest,
+        }
+    }
+}
+
+/// Where `prove` takes its headers from.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Source {
+    /// Fetch each header from the Bitcoin Core node.
+    Rpc,
+    /// Fetch the headers in bulk from the node's REST interface at `--rest-url`.
+    Rest,
+    /// Read a buffer of concatenated 80-byte headers from stdin.
+    Stdin,
+    /// Take each header from a BIP152 compact block given with `--compact-block`.
+    Compact,
+    /// Decode the concatenated header hex an Electrum server returns from
+    /// `blockchain.block.headers`, given with `--electrum-hex`.
+    ElectrumHex,
+}
+
+/// Input read by the `bitcoin-block-verify` guest.
+#[derive(Serialize)]
+struct GuestInput {
+    network: Network,
+    journal_format: JournalFormat,
+    /// Commit the hash of every header rather than only the tip.
+    commit_hashes: bool,
+    /// Check proof of work. Off only for regtest unless `--check-pow` is given.
+    check_pow: bool,
+    /// Height of the last header in `headers`, committed as the tip height.
+    height: u64,
+    anchor: RetargetAnchor,
+    /// Block whose proof of work, and that of the blocks before it, is trusted.
+    assume_valid: Option<[u8; 32]>,
+    /// Signaling threshold from `--require-version-bits` and `--min-signaling`.
+    version_bits: Option<VersionBits>,
+    /// Commit the height of the subsidy halving within the span, from `--detect-halving`.
+    detect_halving: bool,
+    /// Concatenated 80-byte block headers.
+    headers: Vec<u8>,
+}
+
+/// Input read by the `bitcoin-fork-compare` guest.
+#[derive(Serialize)]
+struct ForkInput {
+    network: Network,
+    /// Height of the first header of both branches.
+    start_height: u64,
+    a: Vec<u8>,
+    b: Vec<u8>,
+}
+
+/// Input read by the `bitcoin-sparse-verify` guest.
+#[derive(Serialize)]
+struct SparseInput {
+    network: Network,
+    /// Height of each header, strictly increasing.
+    heights: Vec<u64>,
+    headers: Vec<u8>,
+}
+
+/// Input read by the `bitcoin-stream-verify` guest, written ahead of `chunks` header buffers.
+#[derive(Serialize)]
+struct StreamInput {
+    network: Network,
+    start_height: u64,
+    anchor: RetargetAnchor,
+    chunks: u32,
+}
+
+#[derive(Args)]
+struct ProveArgs {
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    rest: RestArgs,
+
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Prove the node's most recent N blocks, ending at its tip, instead of
+    /// `--start-height..=--end-height`.
+    #[arg(long, value_name = "N", conflicts_with_all = ["start_height", "end_height"])]
+    last: Option<u64>,
+
+    /// Where to take the headers from. With `stdin`, `--end-height` gives the height of the
+    /// last header in the buffer.
+    #[arg(long, value_enum, default_value_t = Source::Rpc)]
+    source: Source,
+
+    /// Files holding serialized `cmpctblock` payloads, in height order, for `--source compact`.
+    #[arg(long)]
+    compact_block: Vec<PathBuf>,
+
+    /// Header hex for `--source electrum-hex`, or a file holding it. `--end-height` gives the
+    /// height of the last header.
+    #[arg(long, value_name = "HEX|PATH")]
+    electrum_hex: Option<String>,
+
+    /// Network the RPC node is running on. Mainnet spans are checked against known
+    /// checkpoints.
+    #[arg(long, value_enum, default_value_t = Network::Regtest)]
+    network: Network,
+
+    /// Check proof of work and difficulty on regtest too. Regtest spans are otherwise proven
+    /// for linkage only, which is a weaker attestation recorded in the journal.
+    #[arg(long)]
+    check_pow: bool,
+
+    /// `nBits` in force for the retarget window containing the first header, in hex. Taken from
+    /// the `--extends` receipt if given, which it must then match, else from the node with
+    /// `--source rpc` or `rest`, otherwise from the first header.
+    #[arg(long, value_parser = parse_bits)]
+    anchor_bits: Option<u32>,
+
+    /// Timestamp of the first header of the retarget window containing the first header. Taken
+    /// like `--anchor-bits`, where the first header is only right if the span starts on a
+    /// retarget boundary.
+    #[arg(long)]
+    anchor_time: Option<u32>,
+
+    /// Layout of the committed journal. `compact` avoids ABI padding for off-chain consumers
+    /// but cannot be checked by the on-chain verifier. The reduced ethabi layouts suit contracts
+    /// that only store the tip, but still commit the anchor for them to check, behind a leading
+    /// tag word naming the layout.
+    #[arg(long, value_enum, default_value_t = JournalFormat::Ethabi)]
+    journal_format: JournalFormat,
+
+    /// Commit the hash of every proven header, not just the tip, and write them to this file
+    /// one per line. Grows the journal by 32 bytes per header.
+    #[arg(long)]
+    hashes_out: Option<PathBuf>,
+
+    /// Write the receipt to this file, bincode-serialized behind a versioned header. With more
+    /// than one span, each receipt goes to this path suffixed with `.<start>-<end>`.
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Also write the committed journal bytes verbatim to this file, or as hex to the results
+    /// if `-`, for decoding with external ABI tooling. With more than one span, each journal
+    /// goes to this path suffixed like `--out`.
+    #[arg(long)]
+    raw_journal: Option<PathBuf>,
+
+    /// Split the range into spans of at most this many headers and prove each in its own
+    /// receipt, printing a manifest of the spans. Spans are cut at retarget boundaries, and each
+    /// one is checked to extend the previous span's proven tip and anchored at the difficulty
+    /// it committed, so the reduced journal layouts cannot be split.
+    #[arg(long, conflicts_with = "dump_input")]
+    max_headers_per_proof: Option<u64>,
+
+    /// Log2 of the most cycles a segment may hold, from 13 to 24. Smaller segments take less
+    /// memory to prove, so a memory-constrained machine can prove a longer span, but each one
+    /// adds a segment receipt to prove and combine, so proving takes longer overall.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(13..=24))]
+    segment_po2: Option<u32>,
+
+    /// Profile the guest's cycles while executing the span and write a pprof profile to this
+    /// file, for finding where `bitcoin-block-verify` spends its cycles.
+    #[arg(long, conflicts_with = "max_headers_per_proof")]
+    profile: Option<PathBuf>,
+
+    /// Print every header about to be proven to stderr, field by field, to debug a span that
+    /// fails to prove.
+    #[arg(long)]
+    dump_headers: bool,
+
+    /// Write the guest input to this file, in the codec the guest reads it with, and exit
+    /// without proving. Feed it back with `ExecutorEnv::builder().write_slice`.
+    #[arg(long)]
+    dump_input: Option<PathBuf>,
+
+    /// A receipt proving the chain up to `--start-height - 1`. It is verified, the new span is
+    /// checked to extend its tip, and the span is anchored at the difficulty it committed as in
+    /// force after that tip, so only the new headers have to be proven.
+    #[arg(long)]
+    extends: Option<PathBuf>,
+
+    /// Trust the proof of work of this block and the ones before it, as Bitcoin Core's
+    /// `-assumevalid` does, if it is in the span. Linkage and difficulty are still checked
+    /// throughout, and the journal records the block so verifiers see the weaker guarantee.
+    #[arg(long)]
+    assume_valid: Option<BlockHash>,
+
+    /// Fail unless at least `--min-signaling` headers of the span signal all of these BIP9
+    /// version bits, given as a hex mask such as `0x4` for bit 2. The mask and the number of
+    /// signaling headers are committed to the journal.
+    #[arg(long, value_parser = parse_bits, requires = "min_signaling")]
+    require_version_bits: Option<u32>,
+
+    /// Number of headers that must signal `--require-version-bits`.
+    #[arg(long, requires = "require_version_bits")]
+    min_signaling: Option<u32>,
+
+    /// Commit the height of the subsidy halving the span contains, if any, and print it. Header
+    /// validity does not depend on halvings; this is for auditing when one was proven.
+    #[arg(long)]
+    detect_halving: bool,
+
+    /// Fail unless the proven tip is this block hash, given in the usual display byte order.
+    #[arg(long)]
+    expect_tip: Option<BlockHash>,
+
+    /// Refuse to run in `RISC0_DEV_MODE`, and fail rather than warn should the prover still
+    /// return a fake receipt.
+    #[arg(long, visible_alias = "require-real-proof")]
+    no_dev_mode: bool,
+
+    /// Prove each span a second time with the local prover, and fail unless both receipts
+    /// verify and commit byte-identical journals. Catches a remote or accelerated backend
+    /// selected with `RISC0_PROVER` diverging from the reference one.
+    #[arg(long, alias = "compare-heights")]
+    compare_provers: bool,
+
+    /// Start without first checking that the node answers and accepts the RPC credentials.
+    #[arg(long)]
+    skip_health_check: bool,
+
+    /// Fetch every block in full and check that the height its coinbase commits to under BIP34
+    /// is the one it was fetched for, catching a node serving the wrong block. Needs
+    /// `--source rpc`, and costs a full block download per header.
+    #[arg(long)]
+    verify_bip34: bool,
+}
+
+#[derive(Parser)]
+struct Cli {
+    /// Write the results to this file instead of stdout, once the command succeeds. Logs,
+    /// warnings and progress always go to stderr.
+    #[arg(long, global = true)]
+    output: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fetch a range of headers from the node and prove it.
+    Prove(ProveArgs),
+    /// Verify a serialized receipt against the guest image ID.
+    Verify {
+        /// Path to a receipt written with `prove --out`.
+        receipt: PathBuf,
+
+        /// Require the journal to be in this `--journal-format`. Every layout is told apart by
+        /// its tag or length, so this is only needed to reject a receipt proven with another.
+        #[arg(long, value_enum)]
+        journal_format: Option<JournalFormat>,
+
+        /// Write the journal bytes verbatim to this file, or as hex to the results if `-`,
+        /// instead of decoding them.
+        #[arg(long, conflicts_with = "journal_format")]
+        raw_journal: Option<PathBuf>,
+    },
+    /// Verify receipts of consecutive spans and check that each extends the one before it.
+    VerifyChain {
+        /// Paths to receipts written with `prove --out`, in height order.
+        #[arg(required = true, num_args = 2..)]
+        receipts: Vec<PathBuf>,
+    },
+    /// Fetch a range of headers and write the buffer to a file without proving.
+    Fetch {
+        #[command(flatten)]
+        rpc: RpcArgs,
+
+        #[command(flatten)]
+        range: RangeArgs,
+
+        /// File to write the concatenated 80-byte headers to.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Prove where two branches known to the node fork, and how much work each adds.
+    ForkCompare {
+        #[command(flatten)]
+        rpc: RpcArgs,
+
+        #[arg(long, value_enum, default_value_t = Network::Regtest)]
+        network: Network,
+
+        /// Height of a block both branches contain, at or below the fork point.
+        #[arg(long)]
+        start_height: u64,
+
+        /// Tip of the first branch.
+        #[arg(long)]
+        branch_a: BlockHash,
+
+        /// Tip of the second branch, e.g. one invalidated during a regtest reorg.
+        #[arg(long)]
+        branch_b: BlockHash,
+    },
+    /// Prove the work behind a sample of headers, such as retarget boundaries plus a recent
+    /// window. Only headers at consecutive heights are checked to link, so this attests to far
+    /// less than `prove`.
+    ProveSparse {
+        #[command(flatten)]
+        rpc: RpcArgs,
+
+        #[arg(long, value_enum, default_value_t = Network::Regtest)]
+        network: Network,
+
+        /// Heights of the sampled headers, in increasing order.
+        #[arg(required = true, num_args = 1..)]
+        heights: Vec<u64>,
+    },
+    /// Prove a range too long for one input buffer by streaming it to the guest in chunks, each
+    /// read and checked on its own, committing only a summary of the span and its chainwork.
+    ProveStream {
+        #[command(flatten)]
+        rpc: RpcArgs,
+
+        #[command(flatten)]
+        range: RangeArgs,
+
+        #[arg(long, value_enum, default_value_t = Network::Regtest)]
+        network: Network,
+
+        /// Number of headers in each chunk written to the guest.
+        #[arg(long, default_value_t = 2016, value_parser = clap::value_parser!(u64).range(1..))]
+        chunk_len: u64,
+    },
+    /// Print each guest's image ID as the `bytes32` verifier contracts expect.
+    ImageIds,
+    /// Decode and print the journal and claim of a serialized receipt.
+    Inspect {
+        /// Path to a receipt written with `prove --out`.
+        receipt: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
     env_logger::init();
-    let mut data = Vec::new();
-    for i in BITCOIN_BLOCK_VERIFY_ID {
-        data.extend(i.to_le_bytes());
-    }
-    let input = {
-        let mut input = Vec::new();
-        let url = "http://127.0.0.1:18443";
-        let auth = Auth::UserPass("admin1".into(), "123".into());
-
-        let client = Client::new(url, auth).unwrap();
-
-        let begin = 10;
-        let end = 15;
-        for height in begin..=end {
-            let header = client
-                .get_block_hash(height)
-                .and_then(|hash| client.get_block_hex(&hash))
-                .unwrap();
-            let data = hex::decode(&header).unwrap();
-            input.extend_from_slice(&data[..80]);
-        }
-        (end, input)
+    if dev_mode() {
+        eprintln!(
+            "WARNING: DEV MODE: proofs are NOT cryptographically valid (RISC0_DEV_MODE is set)."
+        );
+    }
+
+    let cli = Cli::parse();
+    // Results bound for a file are held back until the command succeeds, so a failure never
+    // leaves a partial result behind.
+    let mut stdout = io::stdout().lock();
+    let mut results = Vec::new();
+    let out: &mut dyn Write = match cli.output {
+        Some(_) => &mut results,
+        None => &mut stdout,
     };
 
-    let env = ExecutorEnv::builder()
-        .write(&input)
-        .unwrap()
-        .build()
-        .unwrap();
+    match cli.command {
+        Command::Prove(args) => prove(args, out),
+        Command::Verify {
+            receipt,
+            journal_format,
+            raw_journal,
+        } => {
+            let receipt = read_receipt(&receipt)?;
+            if let Some(path) = raw_journal {
+                receipt
+                    .verify(BITCOIN_BLOCK_VERIFY_ID)
+                    .context("receipt does not verify against the guest image ID")?;
+                write_raw_journal(out, &path, &receipt.journal.bytes)
+            } else {
+                let journal = match journal_format {
+                    Some(format) => {
+                        journal::verify_and_decode_as(&receipt, BITCOIN_BLOCK_VERIFY_ID, format)?
+                    }
+                    None => journal::verify_and_decode(&receipt, BITCOIN_BLOCK_VERIFY_ID)?,
+                };
+                print_journal(out, &journal)
+            }
+        }
+        Command::VerifyChain { receipts } => {
+            for paths in receipts.windows(2) {
+                journal::verify_chain_extends(
+                    &read_receipt(&paths[0])?,
+                    &read_receipt(&paths[1])?,
+                    BITCOIN_BLOCK_VERIFY_ID,
+                )
+                .with_context(|| {
+                    format!(
+                        "{} does not extend {}",
+                        paths[1].display(),
+                        paths[0].display()
+                    )
+                })?;
+            }
+            writeln!(out, "verified a chain of {} receipts", receipts.len())?;
+            Ok(())
+        }
+        Command::Fetch { rpc, range, out } => {
+            range.check()?;
+            let headers = rpc::fetch_headers(&rpc.connect()?, &range)?;
+            fs::write(&out, headers).with_context(|| format!("failed to write {}", out.display()))
+        }
+        Command::ForkCompare {
+            rpc,
+            network,
+            start_height,
+            branch_a,
+            branch_b,
+        } => {
+            check_image_id(
+                "bitcoin-fork-compare",
+                BITCOIN_FORK_COMPARE_ELF,
+                BITCOIN_FORK_COMPARE_ID,
+            )?;
+            let client = rpc.connect()?;
+            let input = ForkInput {
+                network,
+                start_height,
+                a: rpc::fetch_branch(&client, &branch_a, start_height)?,
+                b: rpc::fetch_branch(&client, &branch_b, start_height)?,
+            };
+            let env = ExecutorEnv::builder().write(&input)?.build()?;
+            let receipt = default_prover().prove(env, BITCOIN_FORK_COMPARE_ELF)?;
+            receipt.verify(BITCOIN_FORK_COMPARE_ID)?;
 
-    //set_var("RISC0_PROVER", "ipc");
-    let prover = default_prover();
-    //println!("{}", prover.get_name());
+            let (fork_height, fork_hash, work_a, work_b) =
+                <(U256, B256, U256, U256)>::abi_decode(&receipt.journal.bytes, true)?;
+            writeln!(
+                out,
+                "fork at {} {}, work after it: a {} b {}",
+                fork_height,
+                BlockHash::from_byte_array(fork_hash.0),
+                work_a,
+                work_b
+            )?;
+            Ok(())
+        }
+        Command::ProveSparse {
+            rpc,
+            network,
+            heights,
+        } => {
+            check_image_id(
+                "bitcoin-sparse-verify",
+                BITCOIN_SPARSE_VERIFY_ELF,
+                BITCOIN_SPARSE_VERIFY_ID,
+            )?;
+            let input = SparseInput {
+                network,
+                headers: rpc::fetch_sample(&rpc.connect()?, &heights)?,
+                heights,
+            };
+            let env = ExecutorEnv::builder().write(&input)?.build()?;
+            let receipt = default_prover().prove(env, BITCOIN_SPARSE_VERIFY_ELF)?;
+            receipt.verify(BITCOIN_SPARSE_VERIFY_ID)?;
 
-    let receipt = prover.prove(env, BITCOIN_BLOCK_VERIFY_ELF).unwrap();
-    //println!("{:?}", receipt);
+            let (heights, hashes) =
+                <(Vec<U256>, Vec<B256>)>::abi_decode_params(&receipt.journal.bytes, true)?;
+            for (height, hash) in heights.iter().zip(hashes) {
+                writeln!(
+                    out,
+                    "verified {} {}",
+                    height,
+                    BlockHash::from_byte_array(hash.0)
+                )?;
+            }
+            Ok(())
+        }
+        Command::ProveStream {
+            rpc,
+            range,
+            network,
+            chunk_len,
+        } => prove_stream(out, &rpc, &range, network, chunk_len),
+        Command::ImageIds => {
+            for (guest, image_id) in [
+                ("bitcoin-block-verify", BITCOIN_BLOCK_VERIFY_ID),
+                ("bitcoin-fork-compare", BITCOIN_FORK_COMPARE_ID),
+                ("bitcoin-sparse-verify", BITCOIN_SPARSE_VERIFY_ID),
+                ("bitcoin-stream-verify", BITCOIN_STREAM_VERIFY_ID),
+            ] {
+                writeln!(
+                    out,
+                    "{} 0x{}",
+                    guest,
+                    hex::encode(eth::image_id_to_bytes32(image_id))
+                )?;
+            }
+            Ok(())
+        }
+        Command::Inspect { receipt } => inspect(out, &read_receipt(&receipt)?),
+    }?;
+    out.flush()?;
+    if let Some(path) = &cli.output {
+        fs::write(path, results).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
 
-    let (height, hash) = receipt.journal.decode::<(u64, [u8; 32])>().unwrap();
-    println!("output: {} 0x{}", height, hex::encode(hash));
+fn prove(mut args: ProveArgs, out: &mut dyn Write) -> Result<()> {
+    check_image_id(
+        "bitcoin-block-verify",
+        BITCOIN_BLOCK_VERIFY_ELF,
+        BITCOIN_BLOCK_VERIFY_ID,
+    )?;
+    ensure!(
+        !(args.no_dev_mode && dev_mode()),
+        "RISC0_DEV_MODE is set, unset it to produce a real proof"
+    );
+    // Each span after the first is anchored at what the span before it committed as in force
+    // after its tip, which the reduced layouts leave out.
+    ensure!(
+        !(args.journal_format.is_reduced() && args.max_headers_per_proof.is_some()),
+        "--max-headers-per-proof cannot chain spans in the {:?} journal layout, which commits no \
+         end anchor",
+        args.journal_format
+    );
+    // Building the client does not touch the network, so it is created once up front and shared
+    // by every phase that talks to the node.
+    let client = args.rpc.connect()?;
+    if !args.skip_health_check && (matches!(args.source, Source::Rpc) || args.last.is_some()) {
+        rpc::check_health(&client)?;
+    }
+    if let Some(count) = args.last {
+        args.range = rpc::last_range(&client, count)?;
+        eprintln!(
+            "proving the last {} blocks, {}..={}",
+            count, args.range.start_height, args.range.end_height
+        );
+    }
 
-    receipt.verify(BITCOIN_BLOCK_VERIFY_ID).unwrap();
-    println!(
-        "postStateDigest:{}",
-        receipt
-            .get_claim()
-            .unwrap()
-            .post
-            .as_value()
-            .unwrap()
-            .digest()
+    if matches!(args.source, Source::Rpc | Source::Rest) {
+        args.range.check()?;
+    }
+    let source = header_source(&args, &client)?;
+    let fetch_started = Instant::now();
+    let headers = source.headers(args.range.start_height, args.range.end_height)?;
+    let header_count = (headers.len() / HEADER_LEN) as u64;
+    ensure!(header_count > 0, "no headers to prove");
+    let fetch_time = fetch_started.elapsed();
+    log::info!(
+        "fetched {} headers in {} ms",
+        header_count,
+        fetch_time.as_millis()
     );
+    let start_height = (args.range.end_height + 1)
+        .checked_sub(header_count)
+        .context("more headers than blocks up to --end-height")?;
+    if args.dump_headers {
+        for (i, header) in headers.chunks_exact(HEADER_LEN).enumerate() {
+            eprint!(
+                "{}",
+                headers::describe_header(start_height + i as u64, header)?
+            );
+        }
+    }
+    let anchor = match &args.extends {
+        // The previous proof already committed the difficulty in force after its tip, so the
+        // span is anchored there rather than wherever the node or the flags say.
+        Some(path) => {
+            let chained = check_extension(
+                &read_receipt(path)?,
+                args.network.into(),
+                start_height,
+                &headers,
+            )?;
+            ensure!(
+                args.anchor_bits.map_or(true, |bits| bits == chained.bits)
+                    && args
+                        .anchor_time
+                        .map_or(true, |time| time == chained.window_start_time),
+                "--anchor-bits and --anchor-time disagree with the anchor {:#010x}@{} the \
+                 receipt given with --extends ends at",
+                chained.bits,
+                chained.window_start_time
+            );
+            chained
+        }
+        None => {
+            let window_start = source.window_start(start_height, &headers)?;
+            RetargetAnchor {
+                bits: args.anchor_bits.unwrap_or(window_start.0),
+                window_start_time: args.anchor_time.unwrap_or(window_start.1),
+            }
+        }
+    };
+    log::info!(
+        "anchoring difficulty at nBits {:#010x}, window start time {}",
+        anchor.bits,
+        anchor.window_start_time
+    );
+
+    if let Some(path) = &args.dump_input {
+        let input = guest_input(&args, args.range.end_height, anchor, headers);
+        return fs::write(path, serialize_guest_input(&input)?)
+            .with_context(|| format!("failed to write {}", path.display()));
+    }
+
+    let spans = match args.max_headers_per_proof {
+        Some(max) => chunks::split(start_height, args.range.end_height, max)?,
+        None => vec![(start_height, args.range.end_height)],
+    };
+    let mut hashes = Vec::new();
+    let mut manifest = Vec::new();
+    let mut previous_tip: Option<B256> = None;
+    let mut previous_journal: Option<Journal> = None;
+    let mut prove_time = Duration::ZERO;
+    for &(start, end) in &spans {
+        let offset = (start - start_height) as usize * HEADER_LEN;
+        let span = &headers[offset..(end - start_height + 1) as usize * HEADER_LEN];
+        if let Some(tip) = previous_tip {
+            ensure!(
+                headers::prev_blockhash(headers::leading_header(span)?)? == tip.0,
+                "span {}..={} does not extend the previous span's tip",
+                start,
+                end
+            );
+        }
+        // Chain the difficulty the previous span's guest committed as in force after its tip,
+        // falling back to the buffer if that span went without proof of work checks.
+        let chained = previous_journal
+            .as_ref()
+            .map(|journal| journal.next_anchor(span))
+            .transpose()?
+            .flatten();
+        let span_anchor = match (chained, chunks::window_start(&headers, start_height, start)) {
+            (Some(chained), _) => chained,
+            (None, Some((bits, window_start_time))) if start != start_height => RetargetAnchor {
+                bits,
+                window_start_time,
+            },
+            _ => anchor,
+        };
+
+        let input = guest_input(&args, end, span_anchor, span.to_vec());
+        check_span_input(&input, start)
+            .with_context(|| format!("span {}..={} would not prove", start, end))?;
+        let prove_started = Instant::now();
+        let receipt = prove_span(&args, &input)?;
+        prove_time += prove_started.elapsed();
+        log::info!(
+            "proved {} headers {}..={} in {} ms",
+            end - start + 1,
+            start,
+            end,
+            prove_started.elapsed().as_millis()
+        );
+        let journal =
+            journal::verify_and_decode_as(&receipt, BITCOIN_BLOCK_VERIFY_ID, args.journal_format)?;
+        writeln!(out, "{}", ChainSummary::new(&journal, end - start + 1))?;
+        previous_tip = Some(journal.hash);
+        hashes.extend_from_slice(&journal.hashes);
+
+        let path = args.out.as_ref().map(|out| match spans.len() {
+            1 => out.clone(),
+            _ => PathBuf::from(format!("{}.{}-{}", out.display(), start, end)),
+        });
+        if let Some(path) = &path {
+            fs::write(path, receipt_file::encode(&receipt)?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        if let Some(raw_journal) = &args.raw_journal {
+            let raw_journal = match spans.len() {
+                1 => raw_journal.clone(),
+                _ if raw_journal.as_os_str() == "-" => raw_journal.clone(),
+                _ => PathBuf::from(format!("{}.{}-{}", raw_journal.display(), start, end)),
+            };
+            write_raw_journal(out, &raw_journal, &receipt.journal.bytes)?;
+        }
+        manifest.push((start, end, journal.hash, path));
+        print_receipt(out, receipt)?;
+
+        if end == args.range.end_height && matches!(args.source, Source::Rpc) {
+            rpc::check_tip(&client, &journal)?;
+        }
+        previous_journal = Some(journal);
+    }
+
+    log::info!(
+        "{} headers: fetch {} ms, prove {} ms, total {} ms",
+        header_count,
+        fetch_time.as_millis(),
+        prove_time.as_millis(),
+        (fetch_time + prove_time).as_millis()
+    );
+
+    if let (Some(expected), Some(tip)) = (args.expect_tip, previous_tip) {
+        let tip = BlockHash::from_byte_array(tip.0);
+        ensure!(
+            tip == expected,
+            "proven tip {} is not the expected {}",
+            tip,
+            expected
+        );
+    }
+    if let Some(path) = args.hashes_out {
+        let hashes: String = hashes
+            .iter()
+            .map(|hash| format!("{}\n", BlockHash::from_byte_array(hash.0)))
+            .collect();
+        fs::write(&path, hashes).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    if spans.len() > 1 {
+        writeln!(out, "manifest:")?;
+        for (start, end, tip, path) in manifest {
+            let path = path.map_or("-".into(), |path| path.display().to_string());
+            writeln!(
+                out,
+                "  {}..={} {} {}",
+                start,
+                end,
+                BlockHash::from_byte_array(tip.0),
+                path
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetches `range` from the node one chunk at a time and proves it with the
+/// `bitcoin-stream-verify` guest, writing each chunk to the environment as its own buffer.
+fn prove_stream(
+    out: &mut dyn Write,
+    rpc: &RpcArgs,
+    range: &RangeArgs,
+    network: Network,
+    chunk_len: u64,
+) -> Result<()> {
+    check_image_id(
+        "bitcoin-stream-verify",
+        BITCOIN_STREAM_VERIFY_ELF,
+        BITCOIN_STREAM_VERIFY_ID,
+    )?;
+    range.check()?;
+    let client = rpc.connect()?;
+    let window_start = rpc::fetch_window_start(&client, range.start_height)?;
+    let chunks: Vec<(u64, u64)> = (range.start_height..=range.end_height)
+        .step_by(chunk_len as usize)
+        .map(|start| {
+            (
+                start,
+                start.saturating_add(chunk_len - 1).min(range.end_height),
+            )
+        })
+        .collect();
+    let input = StreamInput {
+        network,
+        start_height: range.start_height,
+        anchor: RetargetAnchor {
+            bits: window_start.bits.to_consensus(),
+            window_start_time: window_start.time,
+        },
+        chunks: chunks.len().try_into().context("too many chunks")?,
+    };
+
+    let mut builder = ExecutorEnv::builder();
+    builder.write(&input)?;
+    for &(start, end) in &chunks {
+        let headers = client.headers(start, end)?;
+        builder.write(&headers)?;
+    }
+    let receipt = default_prover().prove(builder.build()?, BITCOIN_STREAM_VERIFY_ELF)?;
+    receipt.verify(BITCOIN_STREAM_VERIFY_ID)?;
+
+    let (start_height, _, height, hash, median_time_past, .., chain_work) =
+        <(U256, B256, U256, B256, U256, u32, u32, u32, u32, U256)>::abi_decode(
+            &receipt.journal.bytes,
+            true,
+        )?;
+    writeln!(
+        out,
+        "streamed {} chunks: {}..={} tip {} medianTimePast: {} chainWork: {}",
+        chunks.len(),
+        start_height,
+        height,
+        BlockHash::from_byte_array(hash.0),
+        median_time_past,
+        chain_work
+    )?;
+    Ok(())
+}
+
+/// Selects the header provider `--source` names.
+fn header_source<'a>(
+    args: &'a ProveArgs,
+    client: &'a rpc::RpcClient,
+) -> Result<Box<dyn HeaderSource + 'a>> {
+    ensure!(
+        !args.verify_bip34 || matches!(args.source, Source::Rpc),
+        "--verify-bip34 needs --source rpc, the only source with full blocks"
+    );
+    Ok(match args.source {
+        Source::Rpc if args.verify_bip34 => Box::new(rpc::Bip34Checked {
+            client,
+            network: args.network.into(),
+        }),
+        Source::Rpc => Box::new(client),
+        Source::Rest => Box::new(&args.rest),
+        Source::Stdin => Box::new(source::Stdin),
+        Source::Compact => Box::new(source::CompactBlocks(&args.compact_block)),
+        Source::ElectrumHex => Box::new(source::ElectrumHex(
+            args.electrum_hex
+                .as_deref()
+                .context("--source electrum-hex needs --electrum-hex")?,
+        )),
+    })
+}
+
+/// Verifies `previous` and checks that the span of `network` starting at `start_height` extends
+/// its tip, so the new span can be proven on its own. Returns the anchor the new span must be
+/// proven with, the one `previous` committed as in force after its tip.
+fn check_extension(
+    previous: &Receipt,
+    network: validation::Network,
+    start_height: u64,
+    headers: &[u8],
+) -> Result<RetargetAnchor> {
+    let previous = journal::verify_and_decode(previous, BITCOIN_BLOCK_VERIFY_ID)
+        .context("the receipt given with --extends does not verify")?;
+    ensure!(
+        previous.network == network,
+        "the receipt given with --extends was proven for {:?}, not {:?}",
+        previous.network,
+        network
+    );
+    ensure!(
+        previous.pow_checked,
+        "the receipt given with --extends was proven without checking proof of work"
+    );
+    let first_header = headers::leading_header(headers)?;
+    previous.check_extended_by(start_height, first_header)?;
+    previous
+        .next_anchor(first_header)?
+        .context("the receipt given with --extends commits no anchor")
+}
+
+fn guest_input(
+    args: &ProveArgs,
+    height: u64,
+    anchor: RetargetAnchor,
+    headers: Vec<u8>,
+) -> GuestInput {
+    GuestInput {
+        network: args.network,
+        journal_format: args.journal_format,
+        commit_hashes: args.hashes_out.is_some(),
+        check_pow: args.check_pow || !matches!(args.network, Network::Regtest),
+        height,
+        anchor,
+        assume_valid: args.assume_valid.map(|hash| hash.to_byte_array()),
+        version_bits: args
+            .require_version_bits
+            .zip(args.min_signaling)
+            .map(|(mask, min_count)| VersionBits { mask, min_count }),
+        detect_halving: args.detect_halving,
+        headers,
+    }
+}
+
+/// Runs the guest's header checks on the host, so a bad span fails before any proving starts.
+fn check_span_input(input: &GuestInput, start_height: u64) -> Result<()> {
+    if input.journal_format.is_reduced() {
+        ensure!(
+            input.check_pow
+                && input.assume_valid.is_none()
+                && input.version_bits.is_none()
+                && !input.commit_hashes,
+            "--journal-format {:?} can only commit spans whose proof of work is checked in full, \
+             without --assume-valid, --require-version-bits or --hashes-out",
+            input.journal_format
+        );
+        ensure!(
+            !input.detect_halving,
+            "--detect-halving needs a journal format that commits the halving height"
+        );
+    }
+    let network = input.network.into();
+    let headers = HeaderArray::new(&input.headers)
+        .map_err(|err| anyhow::anyhow!("malformed header buffer: {:?}", err))?;
+    if input.check_pow {
+        validation::check_pow_limit(network, headers::leading_header(&input.headers)?)?;
+    }
+    let hashes: Vec<[u8; 32]> = (0..headers.len())
+        .map(|i| *headers.index(i).digest().as_ref())
+        .collect();
+    validation::check_linkage(&input.headers, &hashes)?;
+    if input.check_pow {
+        validation::check_difficulty(network, &input.headers, start_height, input.anchor)?;
+    }
+    if let Some(version_bits) = input.version_bits {
+        validation::check_signaling(&input.headers, version_bits)?;
+    }
+    Ok(())
+}
+
+/// Checks that the image ID embedded at build time is the one of the embedded ELF, which a stale
+/// incremental build can break, before any proving time is spent.
+fn check_image_id(guest: &str, elf: &[u8], id: [u32; 8]) -> Result<()> {
+    let computed = risc0_zkvm::compute_image_id(elf)
+        .with_context(|| format!("failed to compute the {} image ID", guest))?;
+    ensure!(
+        computed == Digest::from(id),
+        "the {} image ID {} does not match its ELF, whose image ID is {}; rebuild the methods \
+         crate with `cargo clean -p methods && cargo build`",
+        guest,
+        Digest::from(id),
+        computed
+    );
+    Ok(())
+}
+
+/// Whether `RISC0_DEV_MODE` is set to a value the prover treats as enabling dev mode.
+fn dev_mode() -> bool {
+    std::env::var("RISC0_DEV_MODE")
+        .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Serializes `input` into the little-endian words the guest's `env::read` decodes. Proving and
+/// `--dump-input` both go through it, so a dumped input is exactly what a proof reads.
+fn serialize_guest_input(input: &GuestInput) -> Result<Vec<u8>> {
+    let words = risc0_zkvm::serde::to_vec(input)?;
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+/// Builds the environment the guest runs a span in, with the `--segment-po2` limit if given and
+/// the profiler writing to `profile`.
+fn span_env(
+    args: &ProveArgs,
+    input: &GuestInput,
+    profile: Option<&Path>,
+) -> Result<ExecutorEnv<'static>> {
+    let mut builder = ExecutorEnv::builder();
+    builder.write_slice(&serialize_guest_input(input)?);
+    if let Some(po2) = args.segment_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    if let Some(path) = profile {
+        builder.enable_profiler(path);
+    }
+    builder.build()
+}
+
+/// Executes the guest on `input` and then proves it.
+///
+/// The prover gives no feedback until it is done, so the guest is first run on its own: a span
+/// the guest rejects fails in seconds, and the segment and cycle counts logged before proving
+/// starts give a sense of how long the proof will take.
+fn prove_span(args: &ProveArgs, input: &GuestInput) -> Result<Receipt> {
+    let env = span_env(args, input, args.profile.as_deref())?;
+    let session = default_executor()
+        .execute(env, BITCOIN_BLOCK_VERIFY_ELF)
+        .context("the guest rejected the span")?;
+    ensure!(
+        session.exit_code == ExitCode::Halted(0),
+        "the guest exited with {:?}",
+        session.exit_code
+    );
+    for (i, segment) in session.segments.iter().enumerate() {
+        log::debug!("segment {}: {} cycles", i, segment.cycles);
+    }
+    log::info!(
+        "executed {} segments, {} cycles; proving",
+        session.segments.len(),
+        session
+            .segments
+            .iter()
+            .map(|segment| u64::from(segment.cycles))
+            .sum::<u64>()
+    );
+
+    if let Some(path) = &args.profile {
+        eprintln!(
+            "wrote the cycle profile to {}, view it with `go tool pprof -http=127.0.0.1:8000 {}`",
+            path.display(),
+            path.display()
+        );
+    }
+
+    // Profiling is only wanted once, so the prover runs without it.
+    let env = span_env(args, input, None)?;
+    let receipt = default_prover().prove(env, BITCOIN_BLOCK_VERIFY_ELF)?;
+    ensure!(
+        !(args.no_dev_mode && matches!(receipt.inner, InnerReceipt::Fake { .. })),
+        "the prover returned a fake receipt, unset RISC0_DEV_MODE to produce a real proof"
+    );
+    if args.compare_provers {
+        compare_with_local_prover(args, input, &receipt)?;
+    }
+    Ok(receipt)
+}
+
+/// Proves `input` again with the local prover and checks that its receipt, like `receipt`,
+/// verifies and commits the same journal.
+fn compare_with_local_prover(
+    args: &ProveArgs,
+    input: &GuestInput,
+    receipt: &Receipt,
+) -> Result<()> {
+    log::info!("proving the span again with the local prover to compare");
+    let local =
+        LocalProver::new("local").prove(span_env(args, input, None)?, BITCOIN_BLOCK_VERIFY_ELF)?;
+    receipt
+        .verify(BITCOIN_BLOCK_VERIFY_ID)
+        .context("the configured prover's receipt does not verify")?;
+    local
+        .verify(BITCOIN_BLOCK_VERIFY_ID)
+        .context("the local prover's receipt does not verify")?;
+    if let Some(divergence) =
+        journal::describe_divergence(&receipt.journal.bytes, &local.journal.bytes)
+    {
+        bail!(
+            "the configured and local provers committed different journals: {}",
+            divergence
+        );
+    }
+    Ok(())
+}
+
+/// Writes `journal` verbatim to `path`, or as hex to `out` if `path` is `-`.
+fn write_raw_journal(out: &mut dyn Write, path: &Path, journal: &[u8]) -> Result<()> {
+    if path.as_os_str() == "-" {
+        writeln!(out, "journal:0x{}", hex::encode(journal))?;
+    } else {
+        fs::write(path, journal).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Prints the claim digest and the seal of `receipt`, and its on-chain calldata if it has any.
+fn print_receipt(out: &mut dyn Write, receipt: Receipt) -> Result<()> {
+    writeln!(
+        out,
+        "postStateDigest:{}",
+        receipt.get_claim()?.post.digest()
+    )?;
+    if matches!(receipt.inner, InnerReceipt::Compact(_)) {
+        let calldata = eth::to_eth_calldata(&receipt, BITCOIN_BLOCK_VERIFY_ID)?;
+        writeln!(out, "calldata:0x{}", hex::encode(calldata))?;
+    }
     match receipt.inner {
-        risc0_zkvm::InnerReceipt::Composite(_receipt) => {}
-        risc0_zkvm::InnerReceipt::Succinct(receipt) => {
-            println!("Succinct:{}", hex::encode(receipt.get_seal_bytes()));
+        InnerReceipt::Composite(_receipt) => {}
+        InnerReceipt::Succinct(receipt) => {
+            writeln!(out, "Succinct:{}", hex::encode(receipt.get_seal_bytes()))?;
+        }
+        InnerReceipt::Compact(receipt) => {
+            writeln!(out, "Compact:{}", hex::encode(receipt.seal))?;
         }
-        risc0_zkvm::InnerReceipt::Compact(receipt) => {
-            println!("Compact:{}", hex::encode(receipt.seal));
+        InnerReceipt::Fake { claim: _ } => {
+            eprintln!("WARNING: this is a fake receipt produced in RISC0_DEV_MODE.");
+            eprintln!("WARNING: it is NOT a cryptographic proof and will not verify on-chain.");
         }
-        risc0_zkvm::InnerReceipt::Fake { claim: _ } => {}
+    }
+    Ok(())
+}
+
+/// Prints every field of the receipt's claim without verifying it, to diagnose receipts that
+/// fail to verify or come from another guest.
+fn inspect(out: &mut dyn Write, receipt: &Receipt) -> Result<()> {
+    let claim = receipt.get_claim()?;
+    let image_id = claim.pre.digest();
+    let guest = if image_id == Digest::from(BITCOIN_BLOCK_VERIFY_ID) {
+        "bitcoin-block-verify"
+    } else if image_id == Digest::from(BITCOIN_FORK_COMPARE_ID) {
+        "bitcoin-fork-compare"
+    } else if image_id == Digest::from(BITCOIN_SPARSE_VERIFY_ID) {
+        "bitcoin-sparse-verify"
+    } else if image_id == Digest::from(BITCOIN_STREAM_VERIFY_ID) {
+        "bitcoin-stream-verify"
+    } else {
+        "unknown guest"
+    };
+    writeln!(out, "claimDigest:{}", claim.digest())?;
+    writeln!(out, "imageId:{} ({})", image_id, guest)?;
+    writeln!(out, "postStateDigest:{}", claim.post.digest())?;
+    writeln!(out, "exitCode:{:?}", claim.exit_code)?;
+    writeln!(out, "inputDigest:{}", claim.input)?;
+    writeln!(out, "outputDigest:{}", claim.output.digest())?;
+    writeln!(
+        out,
+        "journalDigest:{}",
+        Impl::hash_bytes(&receipt.journal.bytes)
+    )?;
+    writeln!(out, "journal:0x{}", hex::encode(&receipt.journal.bytes))?;
+
+    match Journal::decode(&receipt.journal.bytes) {
+        Ok(journal) => print_journal(out, &journal)?,
+        Err(err) => writeln!(
+            out,
+            "journal is not a bitcoin-block-verify journal: {:#}",
+            err
+        )?,
+    }
+    Ok(())
+}
+
+fn parse_bits(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+fn read_receipt(path: &Path) -> Result<Receipt> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    receipt_file::decode(&bytes).with_context(|| format!("failed to load {}", path.display()))
+}
+
+fn print_journal(out: &mut dyn Write, journal: &Journal) -> Result<()> {
+    writeln!(
+        out,
+        "output: {} {} checkpointed: {} powChecked: {} medianTimePast: {} anchor: {:#010x}@{} ({:?} journal)",
+        journal.height,
+        BlockHash::from_byte_array(journal.hash.0),
+        journal.checkpointed,
+        journal.pow_checked,
+        journal.median_time_past,
+        journal.anchor_bits,
+        journal.anchor_time,
+        journal.format
+    )?;
+    writeln!(
+        out,
+        "anchorDifficulty:{}",
+        validation::compact_target_to_difficulty(journal.anchor_bits)
+    )?;
+    writeln!(out, "network:{:?}", journal.network)?;
+    if let Some((height, hash)) = journal.assume_valid {
+        writeln!(
+            out,
+            "assumeValid:{} {}",
+            height,
+            BlockHash::from_byte_array(hash.0)
+        )?;
+    }
+    if let Some((mask, count)) = journal.version_bits {
+        writeln!(out, "versionBits:{:#010x} signaled by {}", mask, count)?;
+    }
+    if let Some(height) = journal.halving_height {
+        writeln!(out, "halving:{}", height)?;
+    }
+    if !journal.format.is_reduced() {
+        writeln!(
+            out,
+            "timeRange:{}..={}",
+            journal.first_time, journal.tip_time
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use validation::{RetargetAnchor, VersionBits};
+
+    use super::{serialize_guest_input, GuestInput, JournalFormat, Network};
+
+    /// The guest's `Input`, with its `JournalFormat` read as the variant index it is encoded as.
+    #[derive(Deserialize)]
+    struct DecodedInput {
+        network: validation::Network,
+        journal_format: u32,
+        commit_hashes: bool,
+        check_pow: bool,
+        height: u64,
+        anchor: RetargetAnchor,
+        assume_valid: Option<[u8; 32]>,
+        version_bits: Option<VersionBits>,
+        detect_halving: bool,
+        headers: Vec<u8>,
+    }
+
+    #[test]
+    fn serializes_guest_input_as_the_guest_reads_it() {
+        let input = GuestInput {
+            network: Network::Testnet,
+            journal_format: JournalFormat::Compact,
+            commit_hashes: true,
+            check_pow: true,
+            height: 15,
+            anchor: RetargetAnchor {
+                bits: 0x1d00ffff,
+                window_start_time: 1_231_006_505,
+            },
+            assume_valid: Some([0xab; 32]),
+            version_bits: Some(VersionBits {
+                mask: 4,
+                min_count: 1,
+            }),
+            detect_halving: true,
+            headers: (0..160).map(|i| i as u8).collect(),
+        };
+        let bytes = serialize_guest_input(&input).unwrap();
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(serialize_guest_input(&input).unwrap(), bytes);
+
+        let words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        let decoded: DecodedInput = risc0_zkvm::serde::from_slice(&words).unwrap();
+        assert_eq!(decoded.network, validation::Network::Testnet);
+        assert_eq!(decoded.journal_format, 1);
+        assert!(decoded.commit_hashes && decoded.check_pow);
+        assert_eq!(decoded.height, 15);
+        assert_eq!(decoded.anchor, input.anchor);
+        assert_eq!(decoded.assume_valid, input.assume_valid);
+        assert_eq!(decoded.version_bits, input.version_bits);
+        assert!(decoded.detect_halving);
+        assert_eq!(decoded.headers, input.headers);
     }
 }
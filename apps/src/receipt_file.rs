@@ -0,0 +1,103 @@
+//! The on-disk format of receipts written with `--out`.
+//!
+//! A file is `MAGIC || u16 LE format version || u16 LE length || risc0-zkvm version || bincode
+//! receipt`, so that a file written by another release is reported as such rather than failing
+//! to deserialize.
+
+use anyhow::{bail, ensure, Context, Result};
+use risc0_zkvm::Receipt;
+
+/// Marks a file as a receipt written by this tool.
+const MAGIC: &[u8; 4] = b"BBVR";
+
+/// Version of the file layout. Bump it whenever the layout or the receipt encoding changes.
+const FORMAT_VERSION: u16 = 1;
+
+/// Serializes `receipt` behind the file header.
+pub fn encode(receipt: &Receipt) -> Result<Vec<u8>> {
+    let risc0_version = risc0_zkvm::VERSION.as_bytes();
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(risc0_version.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(risc0_version);
+    bytes.extend_from_slice(&bincode::serialize(receipt)?);
+    Ok(bytes)
+}
+
+/// Checks the file header and deserializes the receipt after it.
+///
+/// A receipt written by another risc0-zkvm release is still decoded, since the encoding rarely
+/// changes between releases, but a warning names both versions in case it fails to verify.
+pub fn decode(bytes: &[u8]) -> Result<Receipt> {
+    ensure!(
+        bytes.starts_with(MAGIC),
+        "not a receipt file, or one written before receipt files were versioned"
+    );
+    let field = |start: usize| -> Result<u16> {
+        let field = bytes
+            .get(start..start + 2)
+            .context("truncated receipt header")?;
+        Ok(u16::from_le_bytes(field.try_into()?))
+    };
+    let version = field(MAGIC.len())?;
+    if version != FORMAT_VERSION {
+        bail!("receipt format v{}, expected v{}", version, FORMAT_VERSION);
+    }
+    let version_len = field(MAGIC.len() + 2)? as usize;
+    let body_start = MAGIC.len() + 4 + version_len;
+    let risc0_version = bytes
+        .get(MAGIC.len() + 4..body_start)
+        .context("truncated receipt header")?;
+    let risc0_version = String::from_utf8_lossy(risc0_version);
+    if risc0_version != risc0_zkvm::VERSION {
+        log::warn!(
+            "receipt was written with risc0-zkvm {}, this build uses {}",
+            risc0_version,
+            risc0_zkvm::VERSION
+        );
+    }
+    bincode::deserialize(&bytes[body_start..]).context("failed to deserialize receipt")
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::{sha::Digest, InnerReceipt, Receipt, ReceiptClaim};
+
+    use super::{decode, encode, FORMAT_VERSION, MAGIC};
+
+    fn receipt() -> Receipt {
+        let journal = vec![1, 2, 3];
+        let claim = ReceiptClaim::ok(Digest::ZERO, journal.clone());
+        Receipt::new(InnerReceipt::Fake { claim }, journal)
+    }
+
+    #[test]
+    fn round_trips_receipt() {
+        let decoded = decode(&encode(&receipt()).unwrap()).unwrap();
+        assert_eq!(decoded.journal.bytes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_other_format_version() {
+        let mut bytes = encode(&receipt()).unwrap();
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        let err = decode(&bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "receipt format v{}, expected v{}",
+                FORMAT_VERSION + 1,
+                FORMAT_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_unversioned_receipt() {
+        let bytes = bincode::serialize(&receipt()).unwrap();
+        assert!(decode(&bytes).is_err());
+        assert!(decode(&MAGIC[..]).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Fetching block headers from bitcoind's REST interface (`-rest`).
+
+use anyhow::{ensure, Context, Result};
+use apps::headers::{HEADER_LEN, RETARGET_INTERVAL};
+use bitcoin::{block::Header, consensus, hashes::Hash, BlockHash};
+use clap::Args;
+
+use crate::{
+    rpc::{self, RangeArgs},
+    source::HeaderSource,
+};
+
+/// Most headers bitcoind returns from one `/rest/headers` request.
+const MAX_HEADERS_PER_REQUEST: u64 = 2000;
+
+/// Location of the bitcoind REST interface.
+#[derive(Args)]
+pub struct RestArgs {
+    /// Base URL of the bitcoind REST interface, used with `--source rest`.
+    #[arg(long, default_value = "http://127.0.0.1:18443")]
+    rest_url: String,
+}
+
+impl RestArgs {
+    fn get(&self, path: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/rest/{}", self.rest_url.trim_end_matches('/'), path);
+        let response = minreq::get(&url)
+            .send()
+            .with_context(|| format!("failed to reach {}", url))?;
+        ensure!(
+            response.status_code == 200,
+            "{} returned HTTP {} {}",
+            url,
+            response.status_code,
+            response.reason_phrase
+        );
+        Ok(response.into_bytes())
+    }
+
+    fn block_hash(&self, height: u64) -> Result<BlockHash> {
+        let hash = self.get(&format!("blockhashbyheight/{}.bin", height))?;
+        BlockHash::from_slice(&hash)
+            .with_context(|| format!("malformed hash for height {}", height))
+    }
+
+    /// Fetches `count` consecutive headers starting at `hash`, in as many requests as needed.
+    fn headers_from(&self, mut hash: BlockHash, count: u64) -> Result<Vec<u8>> {
+        let mut headers = Vec::with_capacity(count as usize * HEADER_LEN);
+        // Every request after the first starts at the last header already received, since
+        // only its hash is known, and drops it from the response.
+        let mut skip = 0;
+        while ((headers.len() / HEADER_LEN) as u64) < count {
+            let batch =
+                (count - (headers.len() / HEADER_LEN) as u64 + skip).min(MAX_HEADERS_PER_REQUEST);
+            let bytes = self.get(&format!("headers/{}.bin?count={}", hash, batch))?;
+            ensure!(
+                bytes.len() as u64 == batch * HEADER_LEN as u64,
+                "requested {} headers from {}, got {} bytes",
+                batch,
+                hash,
+                bytes.len()
+            );
+            let last: Header = consensus::deserialize(&bytes[bytes.len() - HEADER_LEN..])?;
+            hash = last.block_hash();
+            headers.extend_from_slice(&bytes[skip as usize * HEADER_LEN..]);
+            skip = 1;
+        }
+        Ok(headers)
+    }
+}
+
+/// Fetches the headers in `range` and concatenates them into the buffer the guest reads.
+pub fn fetch_headers(args: &RestArgs, range: &RangeArgs) -> Result<Vec<u8>> {
+    let count = (range.end_height + 1)
+        .checked_sub(range.start_height)
+        .context("--end-height is below --start-height")?;
+    let start = args.block_hash(range.start_height)?;
+    args.headers_from(start, count)
+}
+
+/// Fetches the first header of the retarget window containing `height`, whose `nBits` and
+/// timestamp anchor the guest's difficulty checks.
+pub fn fetch_window_start(args: &RestArgs, height: u64) -> Result<Header> {
+    let window_start = height - height % RETARGET_INTERVAL;
+    let hash = args.block_hash(window_start)?;
+    Ok(consensus::deserialize(&args.headers_from(hash, 1)?)?)
+}
+
+impl HeaderSource for RestArgs {
+    fn headers(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let range = RangeArgs {
+            start_height: start,
+            end_height: end,
+        };
+        let headers = fetch_headers(self, &range)?;
+        rpc::check_span(&range, &headers)?;
+        Ok(headers)
+    }
+
+    fn window_start(&self, height: u64, _headers: &[u8]) -> Result<(u32, u32)> {
+        let header = fetch_window_start(self, height)?;
+        Ok((header.bits.to_consensus(), header.time))
+    }
+}
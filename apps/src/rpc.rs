@@ -0,0 +1,827 @@
+//! Fetching block headers from a Bitcoin Core node over JSON-RPC.
+
+use std::{
+    cell::Cell,
+    fmt, io, thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use apps::{
+    headers::{self, HEADER_LEN, RETARGET_INTERVAL},
+    journal::Journal,
+};
+use bitcoin::{block::Header, consensus, hashes::Hash, Block, BlockHash};
+use bitcoincore_rpc::{
+    json::GetBlockHeaderResult,
+    jsonrpc::{self, simple_http},
+    Client, Error, RpcApi,
+};
+use clap::Args;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::source::HeaderSource;
+
+/// Maximum number of calls sent in one JSON-RPC batch.
+const BATCH_SIZE: usize = 500;
+
+/// Delay before the first retry of a failed RPC call. Doubles after every attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Connection settings for the Bitcoin Core JSON-RPC endpoint.
+#[derive(Args)]
+pub struct RpcArgs {
+    /// Bitcoin Core JSON-RPC endpoint.
+    #[arg(long, default_value = "http://127.0.0.1:18443")]
+    rpc_url: String,
+
+    /// JSON-RPC user name.
+    #[arg(long, default_value = "admin1")]
+    rpc_user: String,
+
+    /// JSON-RPC password.
+    #[arg(long, env = "BITCOIN_RPC_PASSWORD", default_value = "123")]
+    rpc_password: String,
+
+    /// How many times to retry an RPC call that failed with a connection error.
+    #[arg(long, default_value_t = 3)]
+    rpc_retries: u32,
+
+    /// Seconds to wait for the node to answer a single RPC call before it counts as failed.
+    #[arg(long, default_value_t = 15)]
+    rpc_timeout: u64,
+
+    /// Seconds that retries of failed RPC calls may take in total over the whole run, counting
+    /// each failed attempt and the backoff after it. Once they are spent, the next transient
+    /// failure is returned rather than retried, so a flaky node cannot stretch a run by
+    /// `--rpc-retries` backoffs per call.
+    #[arg(long)]
+    rpc_retry_budget: Option<u64>,
+}
+
+impl RpcArgs {
+    pub fn connect(&self) -> Result<RpcClient> {
+        let transport = simple_http::Builder::new()
+            .url(&self.rpc_url)
+            .with_context(|| format!("invalid RPC URL {}", self.rpc_url))?
+            .timeout(Duration::from_secs(self.rpc_timeout))
+            .auth(&self.rpc_user, Some(&self.rpc_password))
+            .build();
+        Ok(RpcClient {
+            client: Client::from_jsonrpc(jsonrpc::Client::with_transport(transport)),
+            retries: self.rpc_retries,
+            retry_budget: self
+                .rpc_retry_budget
+                .map(|budget| Cell::new(Duration::from_secs(budget))),
+        })
+    }
+}
+
+/// A Bitcoin Core client that retries calls failing with transient errors.
+pub struct RpcClient {
+    client: Client,
+    retries: u32,
+    /// What is left of the `--rpc-retry-budget`, shared by every call.
+    retry_budget: Option<Cell<Duration>>,
+}
+
+impl RpcClient {
+    pub fn get_block_count(&self) -> Result<u64, Error> {
+        retry(
+            self.retries,
+            INITIAL_BACKOFF,
+            self.retry_budget.as_ref(),
+            || self.client.get_block_count(),
+        )
+    }
+
+    pub fn get_block_hash(&self, height: u64) -> Result<BlockHash, Error> {
+        retry(
+            self.retries,
+            INITIAL_BACKOFF,
+            self.retry_budget.as_ref(),
+            || self.client.get_block_hash(height),
+        )
+    }
+
+    pub fn get_block_hex(&self, hash: &BlockHash) -> Result<String, Error> {
+        retry(
+            self.retries,
+            INITIAL_BACKOFF,
+            self.retry_budget.as_ref(),
+            || self.client.get_block_hex(hash),
+        )
+    }
+
+    pub fn get_block_header(&self, hash: &BlockHash) -> Result<Header, Error> {
+        retry(
+            self.retries,
+            INITIAL_BACKOFF,
+            self.retry_budget.as_ref(),
+            || self.client.get_block_header(hash),
+        )
+    }
+
+    pub fn get_block_header_info(&self, hash: &BlockHash) -> Result<GetBlockHeaderResult, Error> {
+        retry(
+            self.retries,
+            INITIAL_BACKOFF,
+            self.retry_budget.as_ref(),
+            || self.client.get_block_header_info(hash),
+        )
+    }
+
+    /// Calls `method` once per entry of `params` in JSON-RPC batches of [`BATCH_SIZE`] calls,
+    /// returning the results in order.
+    fn batch<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[serde_json::Value],
+    ) -> Result<Vec<T>, Error> {
+        let jsonrpc = self.client.get_jsonrpc_client();
+        let params = params
+            .iter()
+            .map(serde_json::value::to_raw_value)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut results = Vec::with_capacity(params.len());
+        for chunk in params.chunks(BATCH_SIZE) {
+            let requests: Vec<_> = chunk
+                .iter()
+                .map(|params| jsonrpc.build_request(method, Some(&**params)))
+                .collect();
+            let responses = retry(
+                self.retries,
+                INITIAL_BACKOFF,
+                self.retry_budget.as_ref(),
+                || Ok(jsonrpc.send_batch(&requests)?),
+            )?;
+            for response in responses {
+                let response = response.ok_or_else(|| {
+                    Error::ReturnedError(format!("missing response to {} in batch", method))
+                })?;
+                results.push(response.result()?);
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Runs `op`, retrying up to `retries` times with exponential backoff while it fails with a
+/// transient error. Each failed attempt and the backoff after it are taken from `budget`, and
+/// once it cannot cover them the error is returned instead.
+fn retry<T>(
+    retries: u32,
+    initial_backoff: Duration,
+    budget: Option<&Cell<Duration>>,
+    mut op: impl FnMut() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut backoff = initial_backoff;
+    for _ in 0..retries {
+        let started = Instant::now();
+        match op() {
+            Err(err)
+                if is_transient(&err)
+                    && budget.is_some_and(|budget| !spend(budget, started.elapsed() + backoff)) =>
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    BudgetExhausted {
+                        last_error: describe_timeout(err),
+                    },
+                )
+                .into());
+            }
+            Err(err) if is_transient(&err) => {
+                log::warn!("RPC call failed: {}, retrying in {:?}", err, backoff);
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result.map_err(describe_timeout),
+        }
+    }
+    op().map_err(describe_timeout)
+}
+
+/// The error a call fails with once the `--rpc-retry-budget` cannot cover another retry, carried
+/// as the payload of an [`io::ErrorKind::TimedOut`] error so callers can tell it apart from the
+/// node timing out.
+#[derive(Debug)]
+pub struct BudgetExhausted {
+    /// The transient error the last attempt failed with.
+    pub last_error: Error,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "--rpc-retry-budget exhausted, last error: {}",
+            self.last_error
+        )
+    }
+}
+
+impl std::error::Error for BudgetExhausted {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.last_error)
+    }
+}
+
+/// Takes `cost` from `budget`, or leaves it untouched and returns false if it cannot cover it.
+fn spend(budget: &Cell<Duration>, cost: Duration) -> bool {
+    match budget.get().checked_sub(cost) {
+        Some(left) if !left.is_zero() => {
+            budget.set(left);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Whether `err` is the node failing to answer within `--rpc-timeout`.
+fn is_timeout(err: &Error) -> bool {
+    match err {
+        Error::JsonRpc(jsonrpc::Error::Transport(err)) => matches!(
+            err.downcast_ref::<simple_http::Error>(),
+            Some(simple_http::Error::SocketError(err))
+                if matches!(err.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock)
+        ),
+        _ => false,
+    }
+}
+
+/// Whether `err` is the node rejecting the `--rpc-user` and `--rpc-password` credentials.
+fn is_auth_failure(err: &Error) -> bool {
+    match err {
+        Error::JsonRpc(jsonrpc::Error::Transport(err)) => matches!(
+            err.downcast_ref::<simple_http::Error>(),
+            Some(simple_http::Error::HttpErrorCode(401 | 403))
+        ),
+        _ => false,
+    }
+}
+
+/// Checks that the node answers and accepts the credentials before any work starts, telling
+/// rejected credentials apart from an unreachable node.
+pub fn check_health(client: &RpcClient) -> Result<()> {
+    match client.get_block_count() {
+        Ok(_) => Ok(()),
+        Err(err) if is_auth_failure(&err) => {
+            bail!("RPC auth failed, check --rpc-user and --rpc-password")
+        }
+        Err(err) => Err(err).context("RPC node unreachable"),
+    }
+}
+
+/// Replaces a timeout with an error naming it as such, rather than as an opaque socket error.
+fn describe_timeout(err: Error) -> Error {
+    if is_timeout(&err) {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            "the node did not answer within --rpc-timeout",
+        )
+        .into()
+    } else {
+        err
+    }
+}
+
+/// Whether `err` is worth retrying, as opposed to a logical error such as an unknown block.
+fn is_transient(err: &Error) -> bool {
+    match err {
+        _ if is_auth_failure(err) => false,
+        Error::Io(_) | Error::JsonRpc(jsonrpc::Error::Transport(_)) => true,
+        // RPC_IN_WARMUP: the node is up but still loading.
+        Error::JsonRpc(jsonrpc::Error::Rpc(err)) => err.code == -28,
+        _ => false,
+    }
+}
+
+/// Inclusive range of block heights to fetch.
+#[derive(Args)]
+pub struct RangeArgs {
+    /// Height of the first header.
+    #[arg(long, default_value_t = 10)]
+    pub start_height: u64,
+
+    /// Height of the last header.
+    #[arg(long, default_value_t = 15)]
+    pub end_height: u64,
+}
+
+impl RangeArgs {
+    /// Rejects a range holding no heights. Equal start and end heights are a one-header range.
+    pub fn check(&self) -> Result<()> {
+        ensure!(
+            self.start_height <= self.end_height,
+            "--end-height {} is below --start-height {}, so there are no headers to fetch",
+            self.end_height,
+            self.start_height
+        );
+        Ok(())
+    }
+}
+
+/// Returns the range of the node's most recent `count` blocks, ending at its tip.
+pub fn last_range(client: &RpcClient, count: u64) -> Result<RangeArgs> {
+    ensure!(count > 0, "--last must be positive");
+    let tip = client.get_block_count()?;
+    let start_height = (tip + 1).checked_sub(count).with_context(|| {
+        format!(
+            "--last {} exceeds the {} blocks up to the tip",
+            count,
+            tip + 1
+        )
+    })?;
+    Ok(RangeArgs {
+        start_height,
+        end_height: tip,
+    })
+}
+
+/// Checks that `headers` holds exactly one header per height in `range`, so that the tip height
+/// committed by the guest, `range.end_height`, is the height of the last header.
+pub fn check_span(range: &RangeArgs, headers: &[u8]) -> Result<()> {
+    let count = (headers.len() / HEADER_LEN) as u64;
+    ensure!(
+        count > 0 && range.end_height == range.start_height + count - 1,
+        "{} headers do not span heights {}..={}",
+        count,
+        range.start_height,
+        range.end_height
+    );
+    Ok(())
+}
+
+/// Fetches the headers in `range` and concatenates them into the buffer the guest reads.
+///
+/// The hashes and then the headers are requested in JSON-RPC batches, so a range costs a
+/// couple of round-trips rather than two per block. Nodes that reject a batch as a whole are
+/// queried one call at a time instead, fetching only the header of any block a pruned node no
+/// longer has. A call within a batch failing, such as a height past the tip, fails the fetch just
+/// as it would one call at a time.
+pub fn fetch_headers(client: &RpcClient, range: &RangeArgs) -> Result<Vec<u8>> {
+    match fetch_headers_batched(client, range) {
+        Err(err) if err.downcast_ref::<Error>().is_some_and(is_batch_rejected) => {
+            log::warn!(
+                "node rejected a batched request: {:#}, falling back to one call per block",
+                err
+            );
+            fetch_headers_serial(client, range)
+        }
+        result => result,
+    }
+}
+
+/// Whether `err` is the node rejecting a JSON-RPC batch as a whole, answering it with an HTTP
+/// error or with a body that is not a batch of responses, as a node or proxy without batch
+/// support does.
+fn is_batch_rejected(err: &Error) -> bool {
+    match err {
+        _ if is_auth_failure(err) => false,
+        Error::JsonRpc(jsonrpc::Error::Transport(err)) => matches!(
+            err.downcast_ref::<simple_http::Error>(),
+            Some(simple_http::Error::HttpErrorCode(_) | simple_http::Error::Json(_))
+        ),
+        Error::JsonRpc(jsonrpc::Error::Json(_) | jsonrpc::Error::WrongBatchResponseSize) => true,
+        _ => false,
+    }
+}
+
+fn fetch_headers_batched(client: &RpcClient, range: &RangeArgs) -> Result<Vec<u8>> {
+    let heights: Vec<_> = (range.start_height..=range.end_height)
+        .map(|height| json!([height]))
+        .collect();
+    let hashes: Vec<BlockHash> = client.batch("getblockhash", &heights)?;
+
+    let params: Vec<_> = hashes.iter().map(|hash| json!([hash, false])).collect();
+    let mut headers = Vec::with_capacity(hashes.len() * HEADER_LEN);
+    for header in client.batch::<String>("getblockheader", &params)? {
+        let header = hex::decode(header)?;
+        ensure!(
+            header.len() == HEADER_LEN,
+            "node returned a {}-byte header",
+            header.len()
+        );
+        headers.extend_from_slice(&header);
+    }
+    Ok(headers)
+}
+
+fn fetch_headers_serial(client: &RpcClient, range: &RangeArgs) -> Result<Vec<u8>> {
+    let mut headers = Vec::new();
+    for height in range.start_height..=range.end_height {
+        let hash = client
+            .get_block_hash(height)
+            .with_context(|| format!("failed to fetch block hash at height {}", height))?;
+        match client.get_block_hex(&hash) {
+            Ok(block) => {
+                let block = hex::decode(&block)?;
+                headers.extend_from_slice(headers::leading_header(&block)?);
+            }
+            // A pruned node keeps every header, and the header is all the guest needs.
+            Err(err) if is_pruned(&err) => {
+                log::debug!("block {} is pruned, fetching its header only", height);
+                let header = client
+                    .get_block_header(&hash)
+                    .with_context(|| format!("failed to fetch header at height {}", height))?;
+                headers.extend_from_slice(&consensus::serialize(&header));
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to fetch block at height {}", height))
+            }
+        }
+    }
+    Ok(headers)
+}
+
+/// Whether `err` is a pruned node reporting it no longer has a block's data.
+fn is_pruned(err: &Error) -> bool {
+    match err {
+        // RPC_MISC_ERROR, as `getblock` reports "Block not available (pruned data)".
+        Error::JsonRpc(jsonrpc::Error::Rpc(err)) => {
+            err.code == -1 && err.message.contains("pruned data")
+        }
+        _ => false,
+    }
+}
+
+/// Fetches the first header of the retarget window containing `height`, whose `nBits` and
+/// timestamp anchor the guest's difficulty checks.
+pub fn fetch_window_start(client: &RpcClient, height: u64) -> Result<Header> {
+    let window_start = height - height % RETARGET_INTERVAL;
+    client
+        .get_block_hash(window_start)
+        .and_then(|hash| client.get_block_header(&hash))
+        .with_context(|| format!("failed to fetch header at height {}", window_start))
+}
+
+/// Height from which each network's blocks must commit to their height in the coinbase.
+fn bip34_height(network: validation::Network) -> u64 {
+    match network {
+        validation::Network::Mainnet => 227_931,
+        validation::Network::Testnet => 21_111,
+        validation::Network::Signet | validation::Network::Regtest => 1,
+    }
+}
+
+/// Checks that `block`, fetched for `height`, commits to that height in its coinbase, if BIP34
+/// was active there.
+fn check_bip34(network: validation::Network, height: u64, block: &Block) -> Result<()> {
+    if height < bip34_height(network) {
+        return Ok(());
+    }
+    let committed = block.bip34_block_height().map_err(|err| {
+        anyhow!(
+            "block at height {} has no BIP34 height commitment: {}",
+            height,
+            err
+        )
+    })?;
+    ensure!(
+        committed == height,
+        "block fetched for height {} commits to height {} in its coinbase",
+        height,
+        committed
+    );
+    Ok(())
+}
+
+/// The RPC source for `--verify-bip34`: fetches every block in full, one call at a time, and
+/// checks its coinbase height commitment before keeping only the header.
+pub struct Bip34Checked<'a> {
+    pub client: &'a RpcClient,
+    pub network: validation::Network,
+}
+
+impl HeaderSource for Bip34Checked<'_> {
+    fn headers(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let mut headers = Vec::new();
+        for height in start..=end {
+            let block = self
+                .client
+                .get_block_hash(height)
+                .and_then(|hash| self.client.get_block_hex(&hash))
+                .with_context(|| format!("failed to fetch block at height {}", height))?;
+            let block: Block = consensus::deserialize(&hex::decode(&block)?)
+                .with_context(|| format!("malformed block at height {}", height))?;
+            check_bip34(self.network, height, &block)?;
+            headers.extend_from_slice(&consensus::serialize(&block.header));
+        }
+        check_span(
+            &RangeArgs {
+                start_height: start,
+                end_height: end,
+            },
+            &headers,
+        )?;
+        Ok(headers)
+    }
+
+    fn window_start(&self, height: u64, headers: &[u8]) -> Result<(u32, u32)> {
+        self.client.window_start(height, headers)
+    }
+}
+
+impl HeaderSource for RpcClient {
+    fn headers(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let range = RangeArgs {
+            start_height: start,
+            end_height: end,
+        };
+        let headers = fetch_headers(self, &range)?;
+        check_span(&range, &headers)?;
+        Ok(headers)
+    }
+
+    fn window_start(&self, height: u64, _headers: &[u8]) -> Result<(u32, u32)> {
+        let header = fetch_window_start(self, height)?;
+        Ok((header.bits.to_consensus(), header.time))
+    }
+}
+
+/// Fetches the headers from `start_height` up to `tip` by walking back from `tip`, which need
+/// not be on the node's active chain.
+pub fn fetch_branch(client: &RpcClient, tip: &BlockHash, start_height: u64) -> Result<Vec<u8>> {
+    let tip_height = client
+        .get_block_header_info(tip)
+        .with_context(|| format!("failed to fetch header {}", tip))?
+        .height as u64;
+    ensure!(
+        tip_height >= start_height,
+        "block {} at height {} is below --start-height {}",
+        tip,
+        tip_height,
+        start_height
+    );
+
+    let mut headers = Vec::new();
+    let mut hash = *tip;
+    for _ in start_height..=tip_height {
+        let header = client
+            .get_block_header(&hash)
+            .with_context(|| format!("failed to fetch header {}", hash))?;
+        headers.push(header);
+        hash = header.prev_blockhash;
+    }
+    Ok(headers
+        .iter()
+        .rev()
+        .flat_map(bitcoin::consensus::serialize)
+        .collect())
+}
+
+/// Fetches the header at each of `heights` and concatenates them in the given order.
+pub fn fetch_sample(client: &RpcClient, heights: &[u64]) -> Result<Vec<u8>> {
+    let mut headers = Vec::with_capacity(heights.len() * HEADER_LEN);
+    for &height in heights {
+        let header = client
+            .get_block_hash(height)
+            .and_then(|hash| client.get_block_header(&hash))
+            .with_context(|| format!("failed to fetch header at height {}", height))?;
+        headers.extend_from_slice(&bitcoin::consensus::serialize(&header));
+    }
+    Ok(headers)
+}
+
+/// Warns if the node's block at the committed tip height is no longer the proven tip, e.g.
+/// because the chain reorganized while proving.
+pub fn check_tip(client: &RpcClient, journal: &Journal) -> Result<()> {
+    let hash = client
+        .get_block_hash(journal.height)
+        .with_context(|| format!("failed to fetch block at height {}", journal.height))?;
+    let proven = BlockHash::from_byte_array(journal.hash.0);
+    if hash != proven {
+        log::warn!(
+            "node's block at height {} is {}, not the proven tip {}",
+            journal.height,
+            hash,
+            proven
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io, time::Duration};
+
+    use bitcoincore_rpc::{
+        jsonrpc::{self, simple_http},
+        Error,
+    };
+
+    use apps::headers::HEADER_LEN;
+    use bitcoin::{block::Version, blockdata::constants::genesis_block, ScriptBuf};
+
+    use super::{
+        check_bip34, check_span, is_auth_failure, is_batch_rejected, is_pruned, retry,
+        BudgetExhausted, RangeArgs,
+    };
+
+    /// Whether `err` is a call giving up because the retry budget is spent.
+    fn is_budget_exhausted(err: &Error) -> bool {
+        matches!(err, Error::Io(err) if err.get_ref().is_some_and(|err| err.is::<BudgetExhausted>()))
+    }
+
+    fn transport_error() -> Error {
+        Error::JsonRpc(jsonrpc::Error::Transport("connection reset".into()))
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let calls = Cell::new(0);
+        let result = retry(3, Duration::ZERO, None, || {
+            calls.set(calls.get() + 1);
+            if calls.get() <= 2 {
+                Err(transport_error())
+            } else {
+                Ok(calls.get())
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(2, Duration::ZERO, None, || {
+            calls.set(calls.get() + 1);
+            Err(transport_error())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn stops_retrying_once_budget_is_spent() {
+        let calls = Cell::new(0);
+        let budget = Cell::new(Duration::ZERO);
+        let result: Result<(), _> = retry(3, Duration::ZERO, Some(&budget), || {
+            calls.set(calls.get() + 1);
+            Err(transport_error())
+        });
+
+        assert_eq!(calls.get(), 1);
+        let err = result.unwrap_err();
+        assert!(is_budget_exhausted(&err), "{}", err);
+        assert!(err.to_string().contains("--rpc-retry-budget exhausted"));
+
+        // A budget that is not yet spent leaves the retries as they were.
+        let budget = Cell::new(Duration::from_secs(3600));
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(2, Duration::from_millis(1), Some(&budget), || {
+            calls.set(calls.get() + 1);
+            Err(transport_error())
+        });
+        assert!(!is_budget_exhausted(&result.unwrap_err()));
+        assert_eq!(calls.get(), 3);
+        assert!(budget.get() <= Duration::from_secs(3600) - Duration::from_millis(3));
+    }
+
+    #[test]
+    fn only_retries_spend_the_budget() {
+        let budget = Cell::new(Duration::from_secs(3600));
+        let result = retry(3, Duration::from_secs(60), Some(&budget), || {
+            // However long a call that succeeds takes, it costs the budget nothing.
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(budget.get(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn reports_timeouts() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(1, Duration::ZERO, None, || {
+            calls.set(calls.get() + 1);
+            let err = simple_http::Error::SocketError(io::ErrorKind::WouldBlock.into());
+            Err(Error::JsonRpc(jsonrpc::Error::Transport(Box::new(err))))
+        });
+
+        assert_eq!(calls.get(), 2);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("did not answer within --rpc-timeout"));
+    }
+
+    #[test]
+    fn does_not_retry_auth_failures() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(3, Duration::ZERO, None, || {
+            calls.set(calls.get() + 1);
+            let err = simple_http::Error::HttpErrorCode(401);
+            Err(Error::JsonRpc(jsonrpc::Error::Transport(Box::new(err))))
+        });
+
+        assert!(is_auth_failure(&result.unwrap_err()));
+        assert_eq!(calls.get(), 1);
+        assert!(!is_auth_failure(&transport_error()));
+    }
+
+    #[test]
+    fn does_not_retry_logical_errors() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(3, Duration::ZERO, None, || {
+            calls.set(calls.get() + 1);
+            Err(Error::JsonRpc(jsonrpc::Error::Rpc(
+                jsonrpc::error::RpcError {
+                    code: -8,
+                    message: "Block height out of range".into(),
+                    data: None,
+                },
+            )))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn detects_pruned_blocks() {
+        let rpc_error = |code, message: &str| {
+            Error::JsonRpc(jsonrpc::Error::Rpc(jsonrpc::error::RpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }))
+        };
+
+        assert!(is_pruned(&rpc_error(
+            -1,
+            "Block not available (pruned data)"
+        )));
+        assert!(!is_pruned(&rpc_error(-5, "Block not found")));
+        assert!(!is_pruned(&transport_error()));
+    }
+
+    #[test]
+    fn detects_rejected_batches() {
+        let http_error = |code| {
+            Error::JsonRpc(jsonrpc::Error::Transport(Box::new(
+                simple_http::Error::HttpErrorCode(code),
+            )))
+        };
+
+        assert!(is_batch_rejected(&http_error(500)));
+        assert!(is_batch_rejected(&Error::JsonRpc(
+            jsonrpc::Error::WrongBatchResponseSize
+        )));
+        // The node answering one call of the batch with an error is that call failing.
+        assert!(!is_batch_rejected(&Error::JsonRpc(jsonrpc::Error::Rpc(
+            jsonrpc::error::RpcError {
+                code: -8,
+                message: "Block height out of range".into(),
+                data: None,
+            }
+        ))));
+        assert!(!is_batch_rejected(&http_error(401)));
+        assert!(!is_batch_rejected(&transport_error()));
+    }
+
+    #[test]
+    fn checks_bip34_height_commitment() {
+        let mut block = genesis_block(bitcoin::Network::Bitcoin);
+        let mainnet = validation::Network::Mainnet;
+        // Before activation, a version 1 block commits to nothing.
+        assert!(check_bip34(mainnet, 100, &block).is_ok());
+        assert!(check_bip34(mainnet, 300_000, &block).is_err());
+
+        block.header.version = Version::TWO;
+        block.txdata[0].input[0].script_sig = ScriptBuf::builder().push_int(300_000).into_script();
+        assert!(check_bip34(mainnet, 300_000, &block).is_ok());
+        let err = check_bip34(mainnet, 300_001, &block).unwrap_err();
+        assert!(err.to_string().contains("commits to height 300000"));
+    }
+
+    #[test]
+    fn rejects_empty_range() {
+        let range = |start_height, end_height| RangeArgs {
+            start_height,
+            end_height,
+        };
+
+        assert!(range(15, 15).check().is_ok());
+        assert!(range(10, 15).check().is_ok());
+        assert!(range(16, 15).check().is_err());
+    }
+
+    #[test]
+    fn span_matches_range() {
+        let range = RangeArgs {
+            start_height: 10,
+            end_height: 15,
+        };
+
+        assert!(check_span(&range, &[0; 6 * HEADER_LEN]).is_ok());
+        assert!(check_span(&range, &[0; 5 * HEADER_LEN]).is_err());
+        assert!(check_span(&range, &[]).is_err());
+    }
+}
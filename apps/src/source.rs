@@ -0,0 +1,121 @@
+//! The providers of header buffers that `prove --source` selects between.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use apps::headers::{self, HEADER_LEN};
+
+/// A provider of the concatenated 80-byte headers to prove.
+pub trait HeaderSource {
+    /// Returns the headers from `start` to `end` inclusive. Sources not indexed by height return
+    /// every header they hold and leave it to the caller to check the count.
+    fn headers(&self, start: u64, end: u64) -> Result<Vec<u8>>;
+
+    /// Returns the `nBits` and timestamp of the first header of the retarget window containing
+    /// `height`, the height of the first header of `headers`.
+    ///
+    /// Sources that can only provide the span itself take them from its first header, which is
+    /// only right for a span starting on a retarget boundary unless `--anchor-bits` and
+    /// `--anchor-time` override them.
+    fn window_start(&self, _height: u64, headers: &[u8]) -> Result<(u32, u32)> {
+        let header = headers::leading_header(headers)?;
+        Ok((
+            validation::header_bits(header),
+            validation::timestamp(header),
+        ))
+    }
+}
+
+impl<T: HeaderSource + ?Sized> HeaderSource for &T {
+    fn headers(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        (**self).headers(start, end)
+    }
+
+    fn window_start(&self, height: u64, headers: &[u8]) -> Result<(u32, u32)> {
+        (**self).window_start(height, headers)
+    }
+}
+
+/// A buffer of concatenated headers read from stdin.
+pub struct Stdin;
+
+impl HeaderSource for Stdin {
+    fn headers(&self, _start: u64, _end: u64) -> Result<Vec<u8>> {
+        headers::read_headers(io::stdin().lock())
+    }
+}
+
+/// One header from each BIP152 compact block file, in order.
+pub struct CompactBlocks<'a>(pub &'a [PathBuf]);
+
+impl HeaderSource for CompactBlocks<'_> {
+    fn headers(&self, _start: u64, _end: u64) -> Result<Vec<u8>> {
+        let mut headers = Vec::with_capacity(self.0.len() * HEADER_LEN);
+        for path in self.0 {
+            let block =
+                fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+            headers.extend_from_slice(&headers::header_from_compact_block(&block)?);
+        }
+        Ok(headers)
+    }
+}
+
+/// Concatenated header hex as an Electrum server returns it, given inline or as a file path.
+pub struct ElectrumHex<'a>(pub &'a str);
+
+impl HeaderSource for ElectrumHex<'_> {
+    fn headers(&self, _start: u64, _end: u64) -> Result<Vec<u8>> {
+        let path = Path::new(self.0);
+        if path.is_file() {
+            let hex = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            headers::headers_from_hex(&hex)
+        } else {
+            headers::headers_from_hex(self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use apps::headers::HEADER_LEN;
+    use validation::fixtures::MAINNET_HEADERS;
+
+    use super::{CompactBlocks, ElectrumHex, HeaderSource};
+
+    const GENESIS_HEADER: &str = MAINNET_HEADERS[0];
+
+    #[test]
+    fn reads_electrum_hex_inline() {
+        let hex = GENESIS_HEADER.repeat(2);
+        let headers = ElectrumHex(&hex).headers(0, 1).unwrap();
+        assert_eq!(headers.len(), 2 * HEADER_LEN);
+
+        // Without an authoritative source, the window starts at the first header.
+        assert_eq!(
+            ElectrumHex(&hex).window_start(0, &headers).unwrap(),
+            (0x1d00ffff, 1_231_006_505)
+        );
+        assert!(ElectrumHex(&hex).window_start(0, &[]).is_err());
+    }
+
+    #[test]
+    fn reads_compact_block_files() {
+        let mut block = hex::decode(GENESIS_HEADER).unwrap();
+        block.extend_from_slice(&[0; 8]);
+        block.extend_from_slice(&[0, 0]);
+        let path = std::env::temp_dir().join("source-test-compact-block.bin");
+        fs::write(&path, &block).unwrap();
+
+        let paths = [path.clone(), path.clone()];
+        let headers = CompactBlocks(&paths).headers(0, 1).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(headers.len(), 2 * HEADER_LEN);
+        assert_eq!(headers[..HEADER_LEN], block[..HEADER_LEN]);
+    }
+}
@@ -0,0 +1,96 @@
+//! The result of a successful proving run.
+
+use std::fmt;
+
+use apps::journal::Journal;
+use bitcoin::{hashes::Hash, BlockHash};
+use serde::{Deserialize, Serialize};
+
+/// What a verified receipt attests to about the proven chain.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainSummary {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub header_count: u64,
+    pub tip_hash: BlockHash,
+    /// Whether any mainnet checkpoint fell within the span.
+    pub checkpointed: bool,
+    /// Median time past of the tip, or zero if the span was too short to tell.
+    pub median_time_past: u32,
+    /// Whether proof of work was checked, rather than linkage alone.
+    pub pow_checked: bool,
+    /// Height of the assume-valid block, if it was in the span. Proof of work up to it was
+    /// trusted rather than checked.
+    pub assumed_valid_up_to: Option<u64>,
+}
+
+impl ChainSummary {
+    /// Builds the summary from a decoded journal and the number of headers that were proven.
+    pub fn new(journal: &Journal, header_count: u64) -> Self {
+        Self {
+            start_height: (journal.height + 1).saturating_sub(header_count),
+            end_height: journal.height,
+            header_count,
+            tip_hash: BlockHash::from_byte_array(journal.hash.0),
+            checkpointed: journal.checkpointed,
+            median_time_past: journal.median_time_past,
+            pow_checked: journal.pow_checked,
+            assumed_valid_up_to: journal.assume_valid.map(|(height, _)| height),
+        }
+    }
+}
+
+impl fmt::Display for ChainSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "verified {} headers {}..={}, tip {}",
+            self.header_count, self.start_height, self.end_height, self.tip_hash
+        )?;
+        if self.median_time_past != 0 {
+            write!(f, ", median time past {}", self.median_time_past)?;
+        }
+        if self.checkpointed {
+            write!(f, " (checkpointed)")?;
+        }
+        if !self.pow_checked {
+            write!(f, " (proof of work not checked)")?;
+        }
+        if let Some(height) = self.assumed_valid_up_to {
+            write!(f, " (proof of work assumed valid up to {})", height)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::BlockHash;
+
+    use super::ChainSummary;
+
+    #[test]
+    fn serde_round_trip() {
+        let summary = ChainSummary {
+            start_height: 10,
+            end_height: 15,
+            header_count: 6,
+            tip_hash: BlockHash::from_str(
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            )
+            .unwrap(),
+            checkpointed: false,
+            median_time_past: 1_231_469_744,
+            pow_checked: true,
+            assumed_valid_up_to: Some(12),
+        };
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ChainSummary>(&json).unwrap(),
+            summary
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Mainnet header vectors pinning down what the header chain validation accepts.
+
+use bitcoin_spv::{types::HeaderArray, validatespv::validate_header_chain};
+use validation::{
+    check_difficulty, fixtures::MAINNET_HEADERS, Error, Network, RetargetAnchor, HEADER_LEN,
+};
+
+fn validate(headers: &[&str]) -> bool {
+    let buf: Vec<u8> = headers
+        .iter()
+        .flat_map(|h| hex::decode(h).unwrap())
+        .collect();
+    let headers = HeaderArray::new(&buf).unwrap();
+    validate_header_chain(&headers, true).is_ok()
+}
+
+#[test]
+fn accepts_contiguous_span() {
+    assert!(validate(&MAINNET_HEADERS));
+}
+
+#[test]
+fn rejects_invalid_pow() {
+    // Header 1 with its nonce zeroed, so its hash no longer meets its target.
+    let invalid = format!("{}00000000", &MAINNET_HEADERS[1][..152]);
+    assert!(!validate(&[MAINNET_HEADERS[0], invalid.as_str()]));
+}
+
+#[test]
+fn rejects_broken_linkage() {
+    assert!(!validate(&[
+        MAINNET_HEADERS[0],
+        MAINNET_HEADERS[2],
+        MAINNET_HEADERS[3]
+    ]));
+}
+
+#[test]
+fn retargets_at_window_boundary() {
+    // The last header of the window ending at height 32255, a week after the window started,
+    // and the first header of the next. The difficulty check only reads `nBits` and timestamps.
+    let window_start = 1_231_006_505u32;
+    let span = |bits: u32| {
+        let mut raw = vec![0u8; 2 * HEADER_LEN];
+        raw[68..72].copy_from_slice(&(window_start + 7 * 24 * 60 * 60).to_le_bytes());
+        raw[72..76].copy_from_slice(&0x1d00ffffu32.to_le_bytes());
+        raw[148..152].copy_from_slice(&(window_start + 7 * 24 * 60 * 60 + 600).to_le_bytes());
+        raw[152..156].copy_from_slice(&bits.to_le_bytes());
+        raw
+    };
+    let anchor = RetargetAnchor {
+        bits: 0x1d00ffff,
+        window_start_time: window_start,
+    };
+
+    // A window mined in half the two weeks expected halves the target at height 32256.
+    let next = check_difficulty(Network::Mainnet, &span(0x1c7fff80), 32255, anchor).unwrap();
+    assert_eq!(next.bits, 0x1c7fff80);
+    assert!(matches!(
+        check_difficulty(Network::Mainnet, &span(0x1d00ffff), 32255, anchor),
+        Err(Error::Difficulty {
+            index: 1,
+            claimed: 0x1d00ffff,
+            expected: 0x1c7fff80,
+        })
+    ));
+}
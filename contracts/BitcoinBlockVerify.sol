@@ -19,8 +19,9 @@ pragma solidity ^0.8.20;
 import {IRiscZeroVerifier} from "./IRiscZeroVerifier.sol";
 import {ImageID} from "./ImageID.sol"; // auto-generated contract after running `cargo build`.
 
-/// @title A starter application using RISC Zero.
-/// @notice This basic application holds a number, guaranteed to be even.
+/// @title A light client for the Bitcoin header chain using RISC Zero.
+/// @notice Holds the tip of a Bitcoin header chain, extended only by proven spans of headers
+///         that attach to it.
 /// @dev This contract demonstrates one pattern for offloading the computation of an expensive
 ///      or difficult to implement function to a RISC Zero guest running on Bonsai.
 contract BitcoinBlockVerify {
@@ -28,31 +29,92 @@ contract BitcoinBlockVerify {
     IRiscZeroVerifier public immutable verifier;
     /// @notice Image ID of the only zkVM binary to accept verification from.
     bytes32 public constant imageId = ImageID.BITCOIN_BLOCK_VERIFY_ID;
+    /// @notice Id of the Bitcoin network whose chain is tracked, as the guest commits it:
+    ///         0 for mainnet, 1 for testnet, 2 for signet and 3 for regtest.
+    uint8 public immutable network;
 
-    /// @notice A number that is guaranteed, by the RISC Zero zkVM, to be even.
-    ///         It can be set by calling the `set` function.
+    /// @notice Height of the stored tip. It starts at the checkpoint given to the constructor
+    ///         and is advanced by calling the `set` function.
     uint256 public height;
+    /// @notice Hash of the stored tip, in header digest byte order.
     bytes32 public block_hash;
+    /// @notice `nBits` in force for the block after the stored tip.
+    uint32 public anchorBits;
+    /// @notice Timestamp of the first block of the retarget window containing the block after
+    ///         the stored tip, or zero if that block starts a new window.
+    uint32 public anchorTime;
 
-    /// @notice Initialize the contract, binding it to a specified RISC Zero verifier.
-    constructor(IRiscZeroVerifier _verifier) {
+    /// @notice Initialize the contract, binding it to a specified RISC Zero verifier and Bitcoin
+    ///         network and seeding the tip and its retarget anchor from a trusted checkpoint.
+    constructor(
+        IRiscZeroVerifier _verifier,
+        uint8 _network,
+        uint256 _height,
+        bytes32 _blockHash,
+        uint32 _anchorBits,
+        uint32 _anchorTime
+    ) {
         verifier = _verifier;
-        height = 0;
+        network = _network;
+        height = _height;
+        block_hash = _blockHash;
+        anchorBits = _anchorBits;
+        anchorTime = _anchorTime;
     }
 
-    /// @notice Set the even number stored on the contract. Requires a RISC Zero proof that the number is even.
-    function set(
-        uint256 height_,
-        bytes32 hash_,
-        bytes32 postStateDigest,
-        bytes calldata seal
-    ) public {
-        // Construct the expected journal data. Verify will fail if journal does not match.
-        bytes memory journal = abi.encode(height_, hash_);
+    /// @notice The fixed fields of the guest's ethabi journal. Every member is static, so
+    ///         `abi.encode` of the struct is the journal itself.
+    struct Journal {
+        uint256 height;
+        bytes32 hash;
+        bool checkpointed;
+        uint256 medianTimePast;
+        uint32 anchorBits;
+        uint32 anchorTime;
+        bool powChecked;
+        uint256 assumeValidHeight;
+        bytes32 assumeValidHash;
+        uint256 startHeight;
+        bytes32 parentHash;
+        uint32 versionBitsMask;
+        uint32 versionBitsCount;
+        uint32 endAnchorBits;
+        uint32 endAnchorTime;
+        uint256 halvingHeight;
+        uint32 firstTime;
+        uint32 tipTime;
+        uint8 network;
+    }
+
+    /// @notice Extend the stored tip by a span of headers. Requires a RISC Zero proof that the
+    ///         span attaches to the stored tip and meets the difficulty anchored there.
+    function set(Journal calldata journal, bytes32 postStateDigest, bytes calldata seal) public {
+        // Proof of work checked against another network's rules, such as regtest's trivial
+        // pow_limit, says nothing about this chain.
+        require(journal.network == network, "span was proven for another network");
+        // Spans proven for linkage alone say nothing about the work behind them.
+        require(journal.powChecked, "proof of work was not checked");
+        // Committing no assume-valid block means proof of work was checked for every header.
+        require(journal.assumeValidHash == bytes32(0), "proof of work was assumed valid");
+        require(
+            journal.startHeight == height + 1 && journal.parentHash == block_hash,
+            "span does not extend the stored tip"
+        );
+        // The anchor is chosen by the prover, so the span's difficulty only means something if
+        // it is the one in force after the stored tip. A zero anchor time leaves the window's
+        // start to the span's own first header.
+        require(
+            journal.anchorBits == anchorBits
+                && journal.anchorTime == (anchorTime == 0 ? journal.firstTime : anchorTime),
+            "span is not anchored at the stored tip"
+        );
+        // Verify will fail if the journal does not match the one the guest committed.
         require(
-            verifier.verify(seal, imageId, postStateDigest, sha256(journal))
+            verifier.verify(seal, imageId, postStateDigest, sha256(abi.encode(journal)))
         );
-        height = height_;
-        block_hash = hash_;
+        height = journal.height;
+        block_hash = journal.hash;
+        anchorBits = journal.endAnchorBits;
+        anchorTime = journal.endAnchorTime;
     }
 }
@@ -0,0 +1,20 @@
+//! Feeds arbitrary bytes to the host's header parsing, which reads untrusted input from nodes
+//! and files, and fails on any panic. Malformed input must be rejected with an error instead.
+
+#![no_main]
+
+use apps::headers;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(buf) = headers::read_headers(data) {
+        for header in buf.chunks_exact(headers::HEADER_LEN) {
+            headers::describe_header(0, header).unwrap();
+            headers::prev_blockhash(header).unwrap();
+        }
+    }
+    let _ = headers::headers_from_hex(&String::from_utf8_lossy(data));
+    let _ = headers::header_from_compact_block(data);
+    let _ = headers::describe_header(0, data);
+    let _ = headers::leading_header(data).and_then(headers::prev_blockhash);
+});
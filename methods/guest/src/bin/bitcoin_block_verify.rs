@@ -3,20 +3,469 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use alloy_primitives::U256;
+use alloy_primitives::{B256, U256};
 use alloy_sol_types::SolValue;
-use bitcoin_spv::{types::HeaderArray, validatespv::validate_header_chain};
+use bitcoin_spv::types::HeaderArray;
 use risc0_zkvm::guest::env;
+use serde::Deserialize;
+use validation::{
+    check_difficulty, check_linkage, check_pow_limit, check_signaling, check_work, timestamp,
+    Network, RetargetAnchor, VersionBits, HEADER_LEN,
+};
 risc0_zkvm::guest::entry!(main);
 
+/// Upper bound on the number of headers proven in one session, twenty retarget periods.
+///
+/// Longer buffers are rejected before any validation work so that an oversized input fails
+/// deterministically instead of exhausting the prover's memory. Split longer spans across
+/// several proofs.
+const MAX_HEADERS: usize = 20 * 2016;
+
+/// Exit code the guest halts with when the input holds more than [`MAX_HEADERS`] headers.
+const EXIT_TOO_MANY_HEADERS: u8 = 2;
+
+/// Exit code the guest halts with when two adjacent headers are byte-identical. Linkage would
+/// reject them too, but this cheap check fails such input plainly before any hashing.
+const EXIT_DUPLICATE_HEADER: u8 = 3;
+
+/// Number of blocks between subsidy halvings.
+const HALVING_INTERVAL: u64 = 210_000;
+
+/// Number of preceding blocks whose median timestamp a block's timestamp must exceed.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// First byte of a [`JournalFormat::Compact`] journal. An ethabi journal always starts with a
+/// zero byte, since the committed height is far below 2^248.
+const COMPACT_JOURNAL_TAG: u8 = 1;
+
+/// Length of a [`JournalFormat::Compact`] journal without per-header hashes.
+const COMPACT_JOURNAL_LEN: usize =
+    1 + 8 + 32 + 1 + 4 + 4 + 4 + 1 + 8 + 32 + 8 + 32 + 4 + 4 + 4 + 4 + 8 + 4 + 4 + 1;
+
+/// Known-good mainnet `(height, block hash)` pairs, taken from Bitcoin Core's checkpoints.
+const MAINNET_CHECKPOINTS: &[(u64, [u8; 32])] = &[
+    (
+        11111,
+        hash_from_hex("0000000069e244f73d78e8fd29ba2fd2ed618bd6fa2ee92559f542fdb26e7c1d"),
+    ),
+    (
+        33333,
+        hash_from_hex("000000002dd5588a74784eaa7ab0507a18ad16a236e7b1ce69f00d7ddfb5d0a6"),
+    ),
+    (
+        74000,
+        hash_from_hex("0000000000573993a3c9e41ce34471c079dcf5f52a0e824a81e7f953b8661a20"),
+    ),
+    (
+        105000,
+        hash_from_hex("00000000000291ce28027faea320c8d2b054b2e0fe44a773f3eefb151d6bdc97"),
+    ),
+    (
+        134444,
+        hash_from_hex("00000000000005b12ffd4cd315cd34ffd4a594f430ac814c91184a0d42d2b0fe"),
+    ),
+    (
+        168000,
+        hash_from_hex("000000000000099e61ea72015e79632f216fe6cb33d7899acb35b75c8303b763"),
+    ),
+    (
+        193000,
+        hash_from_hex("000000000000059f452a5f7340de6682a977387c17010ff6e6c3bd83ca8b1317"),
+    ),
+    (
+        210000,
+        hash_from_hex("000000000000048b95347e83192f69cf0366076336c639f9b7228e9ba171342e"),
+    ),
+    (
+        216116,
+        hash_from_hex("00000000000001b4f4b433e81ee46494af945cf96014816a4e2370f11b23df4e"),
+    ),
+    (
+        225430,
+        hash_from_hex("00000000000001c108384350f74090433e7fcf79a606b8e797f065b130575932"),
+    ),
+    (
+        250000,
+        hash_from_hex("000000000000003887df1f29024b06fc2200b55f8af8f35453d7be294df2d214"),
+    ),
+    (
+        279000,
+        hash_from_hex("0000000000000001ae8c72a0b0c301f67e3afca10e819efa9041e458e9bd7e40"),
+    ),
+    (
+        295000,
+        hash_from_hex("00000000000000004d9b4ef50f0f9d686fd69db2e03af35a100370c64632a983"),
+    ),
+];
+
+/// Layout of the committed journal. `height` and `hash` always describe the tip, the last
+/// header of the span, and `start_height` and `parent_hash` where the span attaches: the height
+/// of its first header and that header's `prev_blockhash`.
+///
+/// With `commit_hashes` set, the hash of every header is appended in order: as a trailing
+/// `bytes32[] hashes` parameter for ethabi, or as raw 32-byte hashes for compact.
+///
+/// Every layout ends with the network's [`Network::id`], since an anchor and the proof of work
+/// checked against it only mean anything on the network they were checked for. The reduced
+/// layouts lead with a `uint8 layout` tag holding their position in this enum, 2 to 4, so the
+/// host can tell them apart as it does a compact journal by its leading byte.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+enum JournalFormat {
+    /// `abi.encode(uint256 height, bytes32 hash, bool checkpointed, uint256 medianTimePast,
+    /// uint32 anchorBits, uint32 anchorTime, bool powChecked, uint256 assumeValidHeight,
+    /// bytes32 assumeValidHash, uint256 startHeight, bytes32 parentHash, uint32 versionBitsMask,
+    /// uint32 versionBitsCount, uint32 endAnchorBits, uint32 endAnchorTime,
+    /// uint256 halvingHeight, uint32 firstTime, uint32 tipTime, uint8 network)`, as expected
+    /// on-chain.
+    Ethabi,
+    /// `COMPACT_JOURNAL_TAG || height (u64 LE) || hash || checkpointed (u8) ||
+    /// median_time_past (u32 LE) || anchor.bits (u32 LE) || anchor.window_start_time (u32 LE) ||
+    /// pow_checked (u8) || assume_valid_height (u64 LE) || assume_valid_hash ||
+    /// start_height (u64 LE) || parent_hash || version_bits_mask (u32 LE) ||
+    /// version_bits_count (u32 LE) || end_anchor.bits (u32 LE) ||
+    /// end_anchor.window_start_time (u32 LE) || halving_height (u64 LE) || first_time (u32 LE) ||
+    /// tip_time (u32 LE) || network (u8)`, first_time and tip_time being the timestamps of the
+    /// first header and the tip.
+    Compact,
+    /// `abi.encode(uint8 layout, uint256 height, bytes32 hash, bool checkpointed,
+    /// uint32 anchorBits, uint32 anchorTime, uint8 network)`, for contracts that only track the
+    /// tip.
+    HeightHash,
+    /// `abi.encode(uint8 layout, uint256 startHeight, uint256 height, bytes32 hash,
+    /// bool checkpointed, uint32 anchorBits, uint32 anchorTime, uint8 network)`.
+    StartHeightHash,
+    /// `abi.encode(uint8 layout, uint256 height, bytes32 hash, bool checkpointed,
+    /// uint256 medianTimePast, uint32 anchorBits, uint32 anchorTime, uint8 network)`.
+    HeightHashTime,
+}
+
+impl JournalFormat {
+    /// Whether the layout is one of the reduced ethabi tuples, which leave out the fields
+    /// qualifying how proof of work was checked and so can only commit a span whose proof of
+    /// work was checked in full. They still carry the anchor and the checkpoint flag: the anchor
+    /// is chosen by the host, so the span's difficulty is only as trustworthy as the consumer's
+    /// own check of the anchor.
+    fn is_reduced(self) -> bool {
+        matches!(
+            self,
+            JournalFormat::HeightHash
+                | JournalFormat::StartHeightHash
+                | JournalFormat::HeightHashTime
+        )
+    }
+}
+
+/// Input written by the host.
+#[derive(Deserialize)]
+struct Input {
+    network: Network,
+    journal_format: JournalFormat,
+    /// Commit the hash of every header rather than only the tip. Off by default, since it grows
+    /// the journal by 32 bytes per header.
+    commit_hashes: bool,
+    /// Check each header's proof of work and difficulty. Only regtest may turn this off, to keep
+    /// demos fast; linkage is still checked and the journal records that PoW was skipped.
+    check_pow: bool,
+    /// Height of the last header in `headers`, committed as the tip height.
+    height: u64,
+    anchor: RetargetAnchor,
+    /// Hash of a block, in header digest byte order, whose proof of work and that of the
+    /// headers before it is trusted, as Bitcoin Core's `-assumevalid` does. If it is in the span,
+    /// only the headers after it have their proof of work checked, and its height and hash are
+    /// committed; otherwise it has no effect and zeros are committed.
+    assume_valid: Option<[u8; 32]>,
+    /// Require that enough headers signal these BIP9 version bits. The mask and the number of
+    /// signaling headers are committed; zeros are committed when unset.
+    version_bits: Option<VersionBits>,
+    /// Commit the height of the subsidy halving within the span, if there is one. A span holds
+    /// at most [`MAX_HEADERS`] headers, far fewer than [`HALVING_INTERVAL`], so it can contain at
+    /// most one. Zero is committed when unset or when the span contains none.
+    detect_halving: bool,
+    /// Concatenated 80-byte block headers.
+    headers: Vec<u8>,
+}
+
+/// Decodes a block hash given in display (big-endian) hex into header digest byte order.
+const fn hash_from_hex(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "block hash must be 64 hex digits");
+    let mut hash = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        hash[31 - i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    hash
+}
+
+/// Returns the median of `timestamps`, as Bitcoin Core's `GetMedianTimePast` does.
+fn median(timestamps: &[u32]) -> u32 {
+    let mut sorted = timestamps.to_vec();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// Asserts that every header with 11 predecessors in the span is timestamped after their median,
+/// and returns the tip's median time past.
+///
+/// The median time past is only known if the span holds 11 headers or starts at genesis, and is
+/// reported as zero otherwise.
+fn check_median_time_past(raw: &[u8], start_height: u64) -> u32 {
+    let timestamps: Vec<u32> = raw.chunks_exact(HEADER_LEN).map(timestamp).collect();
+    for i in MEDIAN_TIME_SPAN..timestamps.len() {
+        assert!(
+            timestamps[i] > median(&timestamps[i - MEDIAN_TIME_SPAN..i]),
+            "header {} is not after the median time past",
+            i
+        );
+    }
+
+    if timestamps.len() < MEDIAN_TIME_SPAN && start_height != 0 {
+        return 0;
+    }
+    median(&timestamps[timestamps.len().saturating_sub(MEDIAN_TIME_SPAN)..])
+}
+
+/// Asserts that every checkpoint within the span matches the hash of the header at its height.
+///
+/// Returns whether any checkpoint fell within the span.
+fn check_checkpoints(hashes: &[[u8; 32]], start_height: u64, tip_height: u64) -> bool {
+    let mut exercised = false;
+    for &(height, expected) in MAINNET_CHECKPOINTS {
+        if height < start_height || height > tip_height {
+            continue;
+        }
+        let hash = hashes[(height - start_height) as usize];
+        assert!(hash == expected, "checkpoint mismatch at height {}", height);
+        exercised = true;
+    }
+    exercised
+}
+
 fn main() {
-    let (height, headers): (u64, Vec<u8>) = env::read();
-    let headers = HeaderArray::new(&headers).unwrap();
-    validate_header_chain(&headers, true).unwrap();
-    let raw_header = headers.index(headers.len() - 1);
-    let hash = raw_header.digest().as_ref().clone();
+    let input: Input = env::read();
+    if input.headers.len() > MAX_HEADERS * HEADER_LEN {
+        env::log("input holds more than MAX_HEADERS headers");
+        env::exit(EXIT_TOO_MANY_HEADERS);
+    }
+    // A single header is a valid span: its linkage holds trivially, but its proof of work and
+    // difficulty are still checked.
+    assert!(!input.headers.is_empty(), "no headers to verify");
+    let raw_headers = input.headers.chunks_exact(HEADER_LEN);
+    if raw_headers
+        .clone()
+        .zip(raw_headers.skip(1))
+        .any(|(a, b)| a == b)
+    {
+        env::log("input holds the same header twice in a row");
+        env::exit(EXIT_DUPLICATE_HEADER);
+    }
+    assert!(
+        input.check_pow || input.network == Network::Regtest,
+        "proof of work can only be skipped on regtest"
+    );
+    let headers = HeaderArray::new(&input.headers).unwrap();
+    // Every check below that needs a header's hash takes it from here, so each header is hashed
+    // exactly once.
+    let hashes: Vec<[u8; 32]> = (0..headers.len())
+        .map(|i| *headers.index(i).digest().as_ref())
+        .collect();
+    if input.check_pow {
+        check_pow_limit(input.network, &input.headers[..HEADER_LEN])
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+    check_linkage(&input.headers, &hashes).unwrap_or_else(|err| panic!("{}", err));
+    let assumed = input
+        .assume_valid
+        .and_then(|assume_valid| hashes.iter().position(|&hash| hash == assume_valid));
+    if input.check_pow {
+        // Linkage across the assumed block is already checked, so only the headers after it
+        // need their proof of work validated. Each is checked on its own, rather than with
+        // `validate_header_chain`, so that a failure names the header.
+        let unchecked = assumed.map_or(0, |i| i + 1);
+        for (i, header) in input
+            .headers
+            .chunks_exact(HEADER_LEN)
+            .enumerate()
+            .skip(unchecked)
+        {
+            check_work(i, header, hashes[i]).unwrap_or_else(|err| panic!("{}", err));
+        }
+    }
+    let start_height = (input.height + 1)
+        .checked_sub(headers.len() as u64)
+        .expect("more headers than blocks up to the tip height");
+    let (assume_valid_height, assume_valid_hash) = match assumed {
+        Some(i) => (start_height + i as u64, input.assume_valid.unwrap()),
+        None => (0, [0; 32]),
+    };
+    let checkpointed =
+        input.network == Network::Mainnet && check_checkpoints(&hashes, start_height, input.height);
+    // The anchor for the header after the tip, committed so the next span can be chained to
+    // this one. Zeros are committed when the difficulty went unchecked.
+    let end_anchor = if input.check_pow {
+        check_difficulty(input.network, &input.headers, start_height, input.anchor)
+            .unwrap_or_else(|err| panic!("{}", err))
+    } else {
+        RetargetAnchor {
+            bits: 0,
+            window_start_time: 0,
+        }
+    };
+    let median_time_past = check_median_time_past(&input.headers, start_height);
+    let (version_bits_mask, version_bits_count) = match input.version_bits {
+        Some(version_bits) => {
+            let count = check_signaling(&input.headers, version_bits)
+                .unwrap_or_else(|err| panic!("{}", err));
+            (version_bits.mask, count)
+        }
+        None => (0, 0),
+    };
+    let halving_height = if input.detect_halving {
+        let next = start_height.max(1).div_ceil(HALVING_INTERVAL) * HALVING_INTERVAL;
+        if next <= input.height {
+            next
+        } else {
+            0
+        }
+    } else {
+        0
+    };
+    let parent_hash: [u8; 32] = input.headers[4..36].try_into().unwrap();
+    let first_time = timestamp(&input.headers[..HEADER_LEN]);
+    let tip_time = timestamp(&input.headers[input.headers.len() - HEADER_LEN..]);
+    let hash = hashes[hashes.len() - 1];
+
+    let hashes: Vec<B256> = if input.commit_hashes {
+        hashes.into_iter().map(B256::from).collect()
+    } else {
+        Vec::new()
+    };
 
-    let ret = (U256::from(height), hash.to_vec()).abi_encode();
+    if input.journal_format.is_reduced() {
+        assert!(
+            input.check_pow
+                && assumed.is_none()
+                && input.version_bits.is_none()
+                && !input.commit_hashes,
+            "reduced journal layouts can only commit spans whose proof of work was checked \
+             against the anchor for every header"
+        );
+    }
+    let ret = match input.journal_format {
+        JournalFormat::Ethabi if input.commit_hashes => (
+            U256::from(input.height),
+            B256::from(hash),
+            checkpointed,
+            U256::from(median_time_past),
+            input.anchor.bits,
+            input.anchor.window_start_time,
+            input.check_pow,
+            U256::from(assume_valid_height),
+            B256::from(assume_valid_hash),
+            U256::from(start_height),
+            B256::from(parent_hash),
+            version_bits_mask,
+            version_bits_count,
+            end_anchor.bits,
+            end_anchor.window_start_time,
+            U256::from(halving_height),
+            first_time,
+            tip_time,
+            input.network.id(),
+            hashes,
+        )
+            .abi_encode_params(),
+        JournalFormat::Ethabi => (
+            U256::from(input.height),
+            B256::from(hash),
+            checkpointed,
+            U256::from(median_time_past),
+            input.anchor.bits,
+            input.anchor.window_start_time,
+            input.check_pow,
+            U256::from(assume_valid_height),
+            B256::from(assume_valid_hash),
+            U256::from(start_height),
+            B256::from(parent_hash),
+            version_bits_mask,
+            version_bits_count,
+            end_anchor.bits,
+            end_anchor.window_start_time,
+            U256::from(halving_height),
+            first_time,
+            tip_time,
+            input.network.id(),
+        )
+            .abi_encode(),
+        JournalFormat::Compact => {
+            let mut ret = Vec::with_capacity(COMPACT_JOURNAL_LEN + 32 * hashes.len());
+            ret.push(COMPACT_JOURNAL_TAG);
+            ret.extend_from_slice(&input.height.to_le_bytes());
+            ret.extend_from_slice(&hash);
+            ret.push(checkpointed as u8);
+            ret.extend_from_slice(&median_time_past.to_le_bytes());
+            ret.extend_from_slice(&input.anchor.bits.to_le_bytes());
+            ret.extend_from_slice(&input.anchor.window_start_time.to_le_bytes());
+            ret.push(input.check_pow as u8);
+            ret.extend_from_slice(&assume_valid_height.to_le_bytes());
+            ret.extend_from_slice(&assume_valid_hash);
+            ret.extend_from_slice(&start_height.to_le_bytes());
+            ret.extend_from_slice(&parent_hash);
+            ret.extend_from_slice(&version_bits_mask.to_le_bytes());
+            ret.extend_from_slice(&version_bits_count.to_le_bytes());
+            ret.extend_from_slice(&end_anchor.bits.to_le_bytes());
+            ret.extend_from_slice(&end_anchor.window_start_time.to_le_bytes());
+            ret.extend_from_slice(&halving_height.to_le_bytes());
+            ret.extend_from_slice(&first_time.to_le_bytes());
+            ret.extend_from_slice(&tip_time.to_le_bytes());
+            ret.push(input.network.id());
+            for hash in &hashes {
+                ret.extend_from_slice(hash.as_slice());
+            }
+            ret
+        }
+        JournalFormat::HeightHash => (
+            input.journal_format as u8,
+            U256::from(input.height),
+            B256::from(hash),
+            checkpointed,
+            input.anchor.bits,
+            input.anchor.window_start_time,
+            input.network.id(),
+        )
+            .abi_encode(),
+        JournalFormat::StartHeightHash => (
+            input.journal_format as u8,
+            U256::from(start_height),
+            U256::from(input.height),
+            B256::from(hash),
+            checkpointed,
+            input.anchor.bits,
+            input.anchor.window_start_time,
+            input.network.id(),
+        )
+            .abi_encode(),
+        JournalFormat::HeightHashTime => (
+            input.journal_format as u8,
+            U256::from(input.height),
+            B256::from(hash),
+            checkpointed,
+            U256::from(median_time_past),
+            input.anchor.bits,
+            input.anchor.window_start_time,
+            input.network.id(),
+        )
+            .abi_encode(),
+    };
 
-    env::commit(&ret);
+    env::commit_slice(&ret);
 }
@@ -0,0 +1,63 @@
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::SolValue;
+use bitcoin_spv::types::HeaderArray;
+use risc0_zkvm::guest::env;
+use serde::Deserialize;
+use validation::{chain_work, check_linkage, check_pow_limit, check_work, Network, HEADER_LEN};
+risc0_zkvm::guest::entry!(main);
+
+/// Input written by the host: two branches that start from the same header.
+#[derive(Deserialize)]
+struct Input {
+    network: Network,
+    /// Height of the first header of both branches.
+    start_height: u64,
+    /// Concatenated 80-byte headers of the first branch.
+    a: Vec<u8>,
+    /// Concatenated 80-byte headers of the second branch.
+    b: Vec<u8>,
+}
+
+/// Validates one branch and returns the hash of each of its headers.
+fn validate_branch(network: Network, raw: &[u8]) -> Vec<[u8; 32]> {
+    let headers = HeaderArray::new(raw).unwrap();
+    let hashes: Vec<[u8; 32]> = (0..headers.len())
+        .map(|i| *headers.index(i).digest().as_ref())
+        .collect();
+    check_pow_limit(network, &raw[..HEADER_LEN]).unwrap_or_else(|err| panic!("{}", err));
+    check_linkage(raw, &hashes).unwrap_or_else(|err| panic!("{}", err));
+    for (i, header) in raw.chunks_exact(HEADER_LEN).enumerate() {
+        check_work(i, header, hashes[i]).unwrap_or_else(|err| panic!("{}", err));
+    }
+    hashes
+}
+
+/// Sums the work of the headers in `raw` after the first `skip`.
+fn work_after(raw: &[u8], skip: usize) -> U256 {
+    chain_work(&raw[skip * HEADER_LEN..]).expect("branch work overflows 256 bits")
+}
+
+/// Commits `abi.encode(uint256 forkHeight, bytes32 forkHash, uint256 workA, uint256 workB)`:
+/// the last header both branches share, and the work each branch adds on top of it.
+fn main() {
+    let input: Input = env::read();
+    let a = validate_branch(input.network, &input.a);
+    let b = validate_branch(input.network, &input.b);
+
+    let shared = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+    assert!(shared > 0, "branches do not start from the same header");
+
+    let journal = (
+        U256::from(input.start_height + shared as u64 - 1),
+        B256::from(a[shared - 1]),
+        work_after(&input.a, shared),
+        work_after(&input.b, shared),
+    )
+        .abi_encode();
+    env::commit_slice(&journal);
+}
@@ -0,0 +1,69 @@
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::SolValue;
+use bitcoin_spv::types::HeaderArray;
+use risc0_zkvm::guest::env;
+use serde::Deserialize;
+use validation::{check_pow_limit, check_work, Network, HEADER_LEN};
+risc0_zkvm::guest::entry!(main);
+
+/// Input written by the host: a sample of headers, such as the retarget boundaries plus a recent
+/// window, each tagged with its height.
+#[derive(Deserialize)]
+struct Input {
+    network: Network,
+    /// Height of each header in `headers`, strictly increasing.
+    heights: Vec<u64>,
+    /// Concatenated 80-byte headers.
+    headers: Vec<u8>,
+}
+
+/// Commits `abi.encode(uint256[] heights, bytes32[] hashes)`: every sampled header, each of
+/// which meets the network's pow_limit and its own claimed target.
+///
+/// Headers at consecutive heights must link, but nothing is checked across a gap, so the
+/// journal attests to much less than a contiguous span: the work behind each header, not that
+/// the headers belong to one chain or claim the right difficulty.
+fn main() {
+    let input: Input = env::read();
+    let headers = HeaderArray::new(&input.headers).unwrap();
+    assert!(
+        input.heights.len() == headers.len(),
+        "{} heights for {} headers",
+        input.heights.len(),
+        headers.len()
+    );
+
+    let mut hashes = Vec::with_capacity(headers.len());
+    for (i, header) in input.headers.chunks_exact(HEADER_LEN).enumerate() {
+        let hash: [u8; 32] = *headers.index(i).digest().as_ref();
+        check_pow_limit(input.network, header).unwrap_or_else(|err| panic!("{}", err));
+        check_work(i, header, hash).unwrap_or_else(|err| panic!("{}", err));
+        if i > 0 {
+            let (parent_height, height) = (input.heights[i - 1], input.heights[i]);
+            assert!(
+                height > parent_height,
+                "height {} does not follow {}",
+                height,
+                parent_height
+            );
+            if height == parent_height + 1 {
+                assert!(
+                    header[4..36] == hashes[i - 1],
+                    "header {} does not extend header {}",
+                    i,
+                    i - 1
+                );
+            }
+        }
+        hashes.push(hash);
+    }
+
+    let heights: Vec<U256> = input.heights.iter().map(|&h| U256::from(h)).collect();
+    let hashes: Vec<B256> = hashes.into_iter().map(B256::from).collect();
+    env::commit_slice(&(heights, hashes).abi_encode_params());
+}
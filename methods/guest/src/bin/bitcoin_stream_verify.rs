@@ -0,0 +1,132 @@
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::SolValue;
+use bitcoin_spv::types::HeaderArray;
+use risc0_zkvm::guest::env;
+use serde::Deserialize;
+use validation::{
+    chain_work, check_difficulty, check_linkage, check_pow_limit, check_work, timestamp, Network,
+    RetargetAnchor, HEADER_LEN,
+};
+risc0_zkvm::guest::entry!(main);
+
+/// Number of preceding blocks whose median timestamp a block's timestamp must exceed.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Input written by the host ahead of the headers.
+#[derive(Deserialize)]
+struct Input {
+    network: Network,
+    /// Height of the first header of the first chunk.
+    start_height: u64,
+    anchor: RetargetAnchor,
+    /// Number of header chunks the host writes after the input, each a `Vec<u8>` of
+    /// concatenated 80-byte headers read with its own `env::read`, so that only one chunk is
+    /// held in guest memory at a time.
+    chunks: u32,
+}
+
+/// Returns the median of `timestamps`, as Bitcoin Core's `GetMedianTimePast` does.
+fn median(timestamps: &[u32]) -> u32 {
+    let mut sorted = timestamps.to_vec();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// Commits `abi.encode(uint256 startHeight, bytes32 parentHash, uint256 height, bytes32 hash,
+/// uint256 medianTimePast, uint32 anchorBits, uint32 anchorTime, uint32 endAnchorBits,
+/// uint32 endAnchorTime, uint256 chainWork)`: a span whose proof of work, difficulty, linkage and
+/// timestamps were checked chunk by chunk, and the work it adds to the chain.
+///
+/// Each chunk is checked with the state carried from the one before it: the tip it must extend,
+/// the retarget anchor it starts under and the last eleven timestamps. As with a span that
+/// `check_difficulty` checks on its own, a chunk cannot start on a testnet minimum-difficulty
+/// exception, since its parent's timestamp is not carried.
+fn main() {
+    let input: Input = env::read();
+    assert!(input.chunks > 0, "no headers to verify");
+
+    let mut anchor = input.anchor;
+    let mut height = input.start_height;
+    let mut parent_hash = [0u8; 32];
+    let mut tip = [0u8; 32];
+    let mut timestamps: Vec<u32> = Vec::with_capacity(MEDIAN_TIME_SPAN);
+    let mut proven = 0usize;
+    let mut work = U256::ZERO;
+    for i in 0..input.chunks {
+        let chunk: Vec<u8> = env::read();
+        assert!(!chunk.is_empty(), "chunk {} holds no headers", i);
+        let headers = HeaderArray::new(&chunk).unwrap();
+        let hashes: Vec<[u8; 32]> = (0..headers.len())
+            .map(|i| *headers.index(i).digest().as_ref())
+            .collect();
+        if i == 0 {
+            check_pow_limit(input.network, &chunk[..HEADER_LEN])
+                .unwrap_or_else(|err| panic!("{}", err));
+            parent_hash = chunk[4..36].try_into().unwrap();
+        } else {
+            assert!(
+                chunk[4..36] == tip,
+                "chunk {} does not extend chunk {}",
+                i,
+                i - 1
+            );
+            // The anchor carried across a retarget boundary leaves the window's start time to
+            // the first header of the new window, which starts this chunk.
+            if anchor.window_start_time == 0 {
+                anchor.window_start_time = timestamp(&chunk[..HEADER_LEN]);
+            }
+        }
+        check_linkage(&chunk, &hashes).unwrap_or_else(|err| panic!("chunk {}: {}", i, err));
+        for (j, header) in chunk.chunks_exact(HEADER_LEN).enumerate() {
+            check_work(j, header, hashes[j]).unwrap_or_else(|err| panic!("chunk {}: {}", i, err));
+        }
+        anchor = check_difficulty(input.network, &chunk, height, anchor)
+            .unwrap_or_else(|err| panic!("chunk {}: {}", i, err));
+
+        let carried = timestamps.len();
+        timestamps.extend(chunk.chunks_exact(HEADER_LEN).map(timestamp));
+        for j in carried.max(MEDIAN_TIME_SPAN)..timestamps.len() {
+            assert!(
+                timestamps[j] > median(&timestamps[j - MEDIAN_TIME_SPAN..j]),
+                "header {} of chunk {} is not after the median time past",
+                j - carried,
+                i
+            );
+        }
+        timestamps.drain(..timestamps.len().saturating_sub(MEDIAN_TIME_SPAN));
+
+        work = work
+            .checked_add(chain_work(&chunk).expect("chunk work overflows 256 bits"))
+            .expect("span work overflows 256 bits");
+        height += headers.len() as u64;
+        proven += headers.len();
+        tip = hashes[hashes.len() - 1];
+    }
+
+    // As for a single buffer, the median time past is only known once eleven headers are in the
+    // span or it starts at genesis.
+    let median_time_past = if proven < MEDIAN_TIME_SPAN && input.start_height != 0 {
+        0
+    } else {
+        median(&timestamps)
+    };
+    let journal = (
+        U256::from(input.start_height),
+        B256::from(parent_hash),
+        U256::from(height - 1),
+        B256::from(tip),
+        U256::from(median_time_past),
+        input.anchor.bits,
+        input.anchor.window_start_time,
+        anchor.bits,
+        anchor.window_start_time,
+        work,
+    )
+        .abi_encode();
+    env::commit_slice(&journal);
+}
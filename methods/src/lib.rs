@@ -17,9 +17,750 @@ include!(concat!(env!("OUT_DIR"), "/methods.rs"));
 
 #[cfg(test)]
 mod tests {
-    use alloy_primitives::U256;
+    use alloy_primitives::{B256, U256};
     use alloy_sol_types::SolValue;
-    use risc0_zkvm::{default_executor, ExecutorEnv};
+    use risc0_zkvm::{
+        default_executor,
+        sha::{Impl, Sha256},
+        ExecutorEnv, ExitCode,
+    };
+    use serde::Serialize;
+    use validation::fixtures::MAINNET_HEADERS;
+
+    /// The fixed fields of an ethabi journal, in order.
+    type JournalFields = (
+        U256,
+        B256,
+        bool,
+        U256,
+        u32,
+        u32,
+        bool,
+        U256,
+        B256,
+        U256,
+        B256,
+        u32,
+        u32,
+        u32,
+        u32,
+        U256,
+        u32,
+        u32,
+        u8,
+    );
+
+    #[allow(dead_code)]
+    #[derive(Serialize)]
+    enum Network {
+        Mainnet,
+        Testnet,
+        Signet,
+        Regtest,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize)]
+    enum JournalFormat {
+        Ethabi,
+        Compact,
+        HeightHash,
+        StartHeightHash,
+        HeightHashTime,
+    }
+
+    #[derive(Serialize)]
+    struct RetargetAnchor {
+        bits: u32,
+        window_start_time: u32,
+    }
+
+    #[derive(Serialize)]
+    struct VersionBits {
+        mask: u32,
+        min_count: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Input {
+        network: Network,
+        journal_format: JournalFormat,
+        commit_hashes: bool,
+        check_pow: bool,
+        height: u64,
+        anchor: RetargetAnchor,
+        assume_valid: Option<[u8; 32]>,
+        version_bits: Option<VersionBits>,
+        detect_halving: bool,
+        headers: Vec<u8>,
+    }
+
+    /// Builds an input proving `headers` from genesis, anchored at the first header.
+    fn mainnet_input(headers: &[&str]) -> Input {
+        let headers: Vec<u8> = headers
+            .iter()
+            .flat_map(|h| hex::decode(h).unwrap())
+            .collect();
+        Input {
+            network: Network::Mainnet,
+            journal_format: JournalFormat::Ethabi,
+            commit_hashes: false,
+            check_pow: true,
+            height: (headers.len() / 80) as u64 - 1,
+            anchor: RetargetAnchor {
+                bits: u32::from_le_bytes(headers[72..76].try_into().unwrap()),
+                window_start_time: u32::from_le_bytes(headers[68..72].try_into().unwrap()),
+            },
+            assume_valid: None,
+            version_bits: None,
+            detect_halving: false,
+            headers,
+        }
+    }
+
+    fn execute(input: &Input) -> Vec<u8> {
+        let env = ExecutorEnv::builder()
+            .write(input)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // NOTE: Use the executor to run tests without proving.
+        default_executor()
+            .execute(env, super::BITCOIN_BLOCK_VERIFY_ELF)
+            .unwrap()
+            .journal
+            .bytes
+    }
+
+    #[test]
+    fn accepts_linked_headers() {
+        let journal = execute(&mainnet_input(&MAINNET_HEADERS));
+
+        let (
+            height,
+            hash,
+            checkpointed,
+            median_time_past,
+            anchor_bits,
+            _,
+            pow_checked,
+            _,
+            assumed,
+            start_height,
+            parent,
+            version_bits_mask,
+            _,
+            end_anchor_bits,
+            end_anchor_time,
+            halving_height,
+            first_time,
+            tip_time,
+            network,
+        ) = JournalFields::abi_decode(&journal, true).unwrap();
+        assert_eq!(height, U256::from(3));
+        let mut tip =
+            hex::decode("0000000082b5015589a3fdf2d4baff403e6f0be035a5d9742c1cae6295464449")
+                .unwrap();
+        tip.reverse();
+        assert_eq!(hash.as_slice(), tip.as_slice());
+        assert!(!checkpointed);
+        // The median of the four timestamps from genesis, as `GetMedianTimePast` computes it.
+        assert_eq!(median_time_past, U256::from(1_231_469_744));
+        assert_eq!(anchor_bits, 0x1d00ffff);
+        assert!(pow_checked);
+        assert_eq!(assumed, B256::ZERO);
+        assert_eq!(start_height, U256::ZERO);
+        assert_eq!(parent, B256::ZERO);
+        assert_eq!(version_bits_mask, 0);
+        assert_eq!(end_anchor_bits, 0x1d00ffff);
+        assert_eq!(end_anchor_time, 1_231_006_505);
+        assert_eq!(halving_height, U256::ZERO);
+        assert_eq!(first_time, 1_231_006_505);
+        assert_eq!(tip_time, 1_231_470_173);
+        assert_eq!(network, 0);
+    }
+
+    #[test]
+    fn accepts_single_header() {
+        let journal = execute(&mainnet_input(&MAINNET_HEADERS[..1]));
+
+        let (height, hash, .., pow_checked, _, _, start_height, parent, _, _, _, _, _, _, _, _) =
+            JournalFields::abi_decode(&journal, true).unwrap();
+        assert_eq!(height, U256::ZERO);
+        assert_eq!(start_height, U256::ZERO);
+        assert_eq!(
+            hash,
+            B256::from(header_hash(&hex::decode(MAINNET_HEADERS[0]).unwrap()))
+        );
+        assert_eq!(parent, B256::ZERO);
+        assert!(pow_checked);
+    }
+
+    #[test]
+    #[should_panic(expected = "no headers to verify")]
+    fn rejects_empty_buffer() {
+        let mut input = mainnet_input(&MAINNET_HEADERS[..1]);
+        input.headers.clear();
+        execute(&input);
+    }
+
+    #[test]
+    fn skips_pow_on_regtest() {
+        // Zeroing the nonce breaks the first header's proof of work but not the linkage.
+        let mut input = mainnet_input(&MAINNET_HEADERS[..1]);
+        input.headers[76..80].fill(0);
+        input.network = Network::Regtest;
+        input.check_pow = false;
+        let journal = execute(&input);
+
+        let (.., pow_checked, _, _, _, _, _, _, _, _, _, _, _, network) =
+            JournalFields::abi_decode(&journal, true).unwrap();
+        assert!(!pow_checked);
+        assert_eq!(network, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "proof of work can only be skipped on regtest")]
+    fn requires_pow_off_regtest() {
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.check_pow = false;
+        execute(&input);
+    }
+
+    #[test]
+    #[should_panic(expected = "header 0 claims nBits 0x1d00ffff, expected 0x1c00ffff")]
+    fn rejects_header_off_anchor() {
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.anchor.bits = 0x1c00ffff;
+        execute(&input);
+    }
+
+    /// The genesis header with its `nBits` set to regtest's pow_limit and a matching nonce.
+    const REGTEST_DIFFICULTY_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff7f2000000000";
+
+    /// Regtest's pow_limit, which the headers `mine_regtest_headers` returns all claim.
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    /// Mines `count` linked headers at regtest's pow_limit on top of an all-zero parent, ten
+    /// minutes apart, so the guest can be run on a valid chain without a node.
+    fn mine_regtest_headers(count: u32) -> Vec<u8> {
+        let target =
+            U256::from(REGTEST_BITS & 0x007f_ffff) << (8 * ((REGTEST_BITS >> 24) as usize - 3));
+        let mut headers = Vec::with_capacity(count as usize * 80);
+        let mut parent = [0; 32];
+        for i in 0..count {
+            let mut header = Vec::with_capacity(80);
+            header.extend_from_slice(&0x2000_0000u32.to_le_bytes());
+            header.extend_from_slice(&parent);
+            // Any merkle root will do, as long as it differs between headers.
+            header.extend_from_slice(Impl::hash_bytes(&i.to_le_bytes()).as_bytes());
+            header.extend_from_slice(&(1_296_688_602 + 600 * i).to_le_bytes());
+            header.extend_from_slice(&REGTEST_BITS.to_le_bytes());
+            header.extend_from_slice(&[0; 4]);
+            // About half of all hashes meet the target, so this rarely takes more than a few
+            // tries.
+            for nonce in 0.. {
+                header[76..80].copy_from_slice(&u32::to_le_bytes(nonce));
+                parent = header_hash(&header);
+                if U256::from_le_bytes(parent) <= target {
+                    break;
+                }
+            }
+            headers.extend_from_slice(&header);
+        }
+        headers
+    }
+
+    #[test]
+    fn accepts_mined_regtest_headers() {
+        let headers = mine_regtest_headers(12);
+        let tip = header_hash(&headers[11 * 80..]);
+        let mut input = mainnet_input(&[REGTEST_DIFFICULTY_HEADER]);
+        input.network = Network::Regtest;
+        input.height = 11;
+        input.anchor.window_start_time = 1_296_688_602;
+        input.headers = headers;
+        let journal = execute(&input);
+
+        let (height, hash, _, median_time_past, anchor_bits, _, pow_checked, ..) =
+            JournalFields::abi_decode(&journal, true).unwrap();
+        assert_eq!(height, U256::from(11));
+        assert_eq!(hash, B256::from(tip));
+        assert_eq!(median_time_past, U256::from(1_296_688_602 + 600 * 6));
+        assert_eq!(anchor_bits, REGTEST_BITS);
+        assert!(pow_checked);
+    }
+
+    #[test]
+    fn commits_halving_height() {
+        let mut input = mainnet_input(&[REGTEST_DIFFICULTY_HEADER]);
+        input.network = Network::Regtest;
+        input.height = 210_001;
+        input.anchor.window_start_time = 1_296_688_602;
+        input.headers = mine_regtest_headers(3);
+        let (.., halving_height, _, _, _) =
+            JournalFields::abi_decode(&execute(&input), true).unwrap();
+        assert_eq!(halving_height, U256::ZERO);
+
+        input.detect_halving = true;
+        let (.., halving_height, _, _, _) =
+            JournalFields::abi_decode(&execute(&input), true).unwrap();
+        assert_eq!(halving_height, U256::from(210_000));
+
+        // The span 210_001..=210_003 starts after the halving.
+        input.height = 210_003;
+        let (.., halving_height, _, _, _) =
+            JournalFields::abi_decode(&execute(&input), true).unwrap();
+        assert_eq!(halving_height, U256::ZERO);
+    }
+
+    #[test]
+    fn accepts_header_at_pow_limit() {
+        let mut input = mainnet_input(&[REGTEST_DIFFICULTY_HEADER]);
+        input.network = Network::Regtest;
+        execute(&input);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the network's pow_limit")]
+    fn rejects_header_above_pow_limit() {
+        execute(&mainnet_input(&[REGTEST_DIFFICULTY_HEADER]));
+    }
+
+    #[test]
+    fn halts_on_too_many_headers() {
+        // One more than the guest's MAX_HEADERS. The contents are never looked at.
+        let input = Input {
+            height: 20 * 2016,
+            headers: vec![0; (20 * 2016 + 1) * 80],
+            ..mainnet_input(&MAINNET_HEADERS[..1])
+        };
+        let env = ExecutorEnv::builder()
+            .write(&input)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let session_info = default_executor()
+            .execute(env, super::BITCOIN_BLOCK_VERIFY_ELF)
+            .unwrap();
+        assert_eq!(session_info.exit_code, ExitCode::Halted(2));
+    }
+
+    #[test]
+    fn halts_on_duplicate_header() {
+        let headers = [MAINNET_HEADERS[0], MAINNET_HEADERS[1], MAINNET_HEADERS[1]];
+        let env = ExecutorEnv::builder()
+            .write(&mainnet_input(&headers))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let session_info = default_executor()
+            .execute(env, super::BITCOIN_BLOCK_VERIFY_ELF)
+            .unwrap();
+        assert_eq!(session_info.exit_code, ExitCode::Halted(3));
+    }
+
+    #[test]
+    fn commits_all_hashes() {
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.commit_hashes = true;
+        let journal = execute(&input);
+
+        let ((_, tip, ..), hashes) =
+            <(JournalFields, Vec<B256>)>::abi_decode_params(&journal, true).unwrap();
+        assert_eq!(hashes.len(), MAINNET_HEADERS.len());
+        assert_eq!(hashes.last(), Some(&tip));
+    }
+
+    #[test]
+    fn commits_compact_journal() {
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.journal_format = JournalFormat::Compact;
+        let journal = execute(&input);
+
+        assert_eq!(
+            journal.len(),
+            1 + 8 + 32 + 1 + 4 + 4 + 4 + 1 + 8 + 32 + 8 + 32 + 4 + 4 + 4 + 4 + 8 + 4 + 4 + 1
+        );
+        assert_eq!(journal[0], 1);
+        assert_eq!(journal[1..9], 3u64.to_le_bytes());
+        assert_eq!(journal[41], 0);
+        assert_eq!(journal[42..46], 1_231_469_744u32.to_le_bytes());
+        assert_eq!(journal[46..50], 0x1d00ffffu32.to_le_bytes());
+        assert_eq!(journal[54], 1);
+        assert_eq!(journal[63..95], [0; 32]);
+        assert_eq!(journal[95..103], 0u64.to_le_bytes());
+        assert_eq!(journal[135..143], [0; 8]);
+        assert_eq!(journal[143..147], 0x1d00ffffu32.to_le_bytes());
+        assert_eq!(journal[151..159], [0; 8]);
+        assert_eq!(journal[159..163], 1_231_006_505u32.to_le_bytes());
+        assert_eq!(journal[163..167], 1_231_470_173u32.to_le_bytes());
+        assert_eq!(journal[167], 0);
+    }
+
+    #[test]
+    fn commits_reduced_journals() {
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        let (_, tip, ..) = JournalFields::abi_decode(&execute(&input), true).unwrap();
+        // Genesis starts the span, so it is its own anchor.
+        let anchor = (0x1d00ffffu32, 1_231_006_505u32);
+
+        input.journal_format = JournalFormat::HeightHash;
+        let journal = execute(&input);
+        assert_eq!(journal.len(), 7 * 32);
+        let (layout, height, hash, checkpointed, anchor_bits, anchor_time, network) =
+            <(u8, U256, B256, bool, u32, u32, u8)>::abi_decode(&journal, true).unwrap();
+        assert_eq!(layout, 2);
+        assert_eq!((height, hash), (U256::from(3), tip));
+        assert!(!checkpointed);
+        assert_eq!((anchor_bits, anchor_time), anchor);
+        assert_eq!(network, 0);
+
+        input.journal_format = JournalFormat::StartHeightHash;
+        let journal = execute(&input);
+        let (layout, start_height, height, hash, _, anchor_bits, anchor_time, _) =
+            <(u8, U256, U256, B256, bool, u32, u32, u8)>::abi_decode(&journal, true).unwrap();
+        assert_eq!(layout, 3);
+        assert_eq!(start_height, U256::ZERO);
+        assert_eq!((height, hash), (U256::from(3), tip));
+        assert_eq!((anchor_bits, anchor_time), anchor);
+
+        input.journal_format = JournalFormat::HeightHashTime;
+        let journal = execute(&input);
+        let (layout, .., median_time_past, anchor_bits, anchor_time, _) =
+            <(u8, U256, B256, bool, U256, u32, u32, u8)>::abi_decode(&journal, true).unwrap();
+        assert_eq!(layout, 4);
+        assert_eq!(median_time_past, U256::from(1_231_469_744));
+        assert_eq!((anchor_bits, anchor_time), anchor);
+    }
+
+    #[test]
+    #[should_panic(expected = "reduced journal layouts can only commit spans whose proof of work")]
+    fn rejects_reduced_journal_of_assumed_span() {
+        let mut input = mainnet_input(&MAINNET_HEADERS[..1]);
+        input.assume_valid = Some(header_hash(&input.headers));
+        input.journal_format = JournalFormat::HeightHash;
+        execute(&input);
+    }
+
+    /// Returns the double-SHA256 hash of a serialized header, in header digest byte order.
+    fn header_hash(header: &[u8]) -> [u8; 32] {
+        let hash = Impl::hash_bytes(Impl::hash_bytes(header).as_bytes());
+        hash.as_bytes().try_into().unwrap()
+    }
+
+    #[test]
+    fn trusts_pow_up_to_assume_valid_block() {
+        // Zeroing the nonce breaks the header's proof of work, which is trusted once its hash
+        // is given as the assume-valid block.
+        let mut input = mainnet_input(&MAINNET_HEADERS[..1]);
+        input.headers[76..80].fill(0);
+        let hash = header_hash(&input.headers);
+        input.assume_valid = Some(hash);
+        let journal = execute(&input);
+
+        let (.., pow_checked, assumed_height, assumed_hash, _, _, _, _, _, _, _, _, _, _) =
+            JournalFields::abi_decode(&journal, true).unwrap();
+        assert!(pow_checked);
+        assert_eq!(assumed_height, U256::ZERO);
+        assert_eq!(assumed_hash, B256::from(hash));
+    }
+
+    #[test]
+    #[should_panic]
+    fn checks_pow_after_assume_valid_block() {
+        let mut input = mainnet_input(&MAINNET_HEADERS[..2]);
+        input.headers[156..160].fill(0);
+        input.assume_valid = Some(header_hash(&input.headers[..80]));
+        execute(&input);
+    }
+
+    #[test]
+    fn chains_end_anchor_into_next_span() {
+        let first = execute(&mainnet_input(&MAINNET_HEADERS[..2]));
+        let (.., first_end_bits, first_end_time, _, _, _, _) =
+            JournalFields::abi_decode(&first, true).unwrap();
+
+        // The second span starts after the first one's tip, anchored at what it committed.
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.headers.drain(..2 * 80);
+        input.anchor = RetargetAnchor {
+            bits: first_end_bits,
+            window_start_time: first_end_time,
+        };
+        let second = execute(&input);
+
+        let (_, _, _, _, anchor_bits, anchor_time, .., start_height, _, _, _, _, _, _, _, _, _) =
+            JournalFields::abi_decode(&second, true).unwrap();
+        assert_eq!(start_height, U256::from(2));
+        assert_eq!((anchor_bits, anchor_time), (first_end_bits, first_end_time));
+    }
+
+    #[test]
+    fn counts_version_bit_signaling() {
+        // Setting BIP9 bit 2 breaks the header's proof of work, so prove it on regtest without.
+        let mut input = mainnet_input(&MAINNET_HEADERS[..1]);
+        input.headers[0..4].copy_from_slice(&0x2000_0004u32.to_le_bytes());
+        input.network = Network::Regtest;
+        input.check_pow = false;
+        input.version_bits = Some(VersionBits {
+            mask: 1 << 2,
+            min_count: 1,
+        });
+        let journal = execute(&input);
+
+        let (.., mask, count, _, _, _, _, _, _) =
+            JournalFields::abi_decode(&journal, true).unwrap();
+        assert_eq!(mask, 1 << 2);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "0 headers signal version bits 0x00000004, 1 required")]
+    fn requires_version_bit_signaling() {
+        // The early mainnet headers are version 1, from before BIP9.
+        let mut input = mainnet_input(&MAINNET_HEADERS);
+        input.version_bits = Some(VersionBits {
+            mask: 1 << 2,
+            min_count: 1,
+        });
+        execute(&input);
+    }
+
+    /// Runs the guest on `input`, returning the error it was rejected with.
+    fn execute_err(input: &Input) -> String {
+        let env = ExecutorEnv::builder()
+            .write(input)
+            .unwrap()
+            .build()
+            .unwrap();
+        default_executor()
+            .execute(env, super::BITCOIN_BLOCK_VERIFY_ELF)
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn rejects_corrupted_nonces() {
+        // The unmodified span is accepted.
+        let input = mainnet_input(&MAINNET_HEADERS);
+        execute(&input);
+
+        // An interior header's new hash no longer matches its child's `prev_blockhash`, and
+        // linkage is checked before proof of work.
+        let mut interior = mainnet_input(&MAINNET_HEADERS);
+        interior.headers[80 + 76] ^= 0x01;
+        let err = execute_err(&interior);
+        assert!(err.contains("header 2 does not extend header 1"), "{}", err);
+
+        // The tip has no child, so only its proof of work catches it.
+        let mut tip = mainnet_input(&MAINNET_HEADERS);
+        tip.headers[3 * 80 + 76] ^= 0x01;
+        let err = execute_err(&tip);
+        assert!(
+            err.contains("header 3 does not meet its claimed target"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "header 1 does not extend header 0")]
+    fn rejects_reordered_headers() {
+        let mut headers = MAINNET_HEADERS;
+        headers.swap(1, 2);
+        execute(&mainnet_input(&headers));
+    }
+
+    #[derive(Serialize)]
+    struct ForkInput {
+        network: Network,
+        start_height: u64,
+        a: Vec<u8>,
+        b: Vec<u8>,
+    }
+
+    fn compare_forks(a: &[&str], b: &[&str]) -> Vec<u8> {
+        let input = ForkInput {
+            network: Network::Mainnet,
+            start_height: 0,
+            a: mainnet_input(a).headers,
+            b: mainnet_input(b).headers,
+        };
+        let env = ExecutorEnv::builder()
+            .write(&input)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        default_executor()
+            .execute(env, super::BITCOIN_FORK_COMPARE_ELF)
+            .unwrap()
+            .journal
+            .bytes
+    }
+
+    #[test]
+    fn finds_fork_point() {
+        let journal = compare_forks(&MAINNET_HEADERS, &MAINNET_HEADERS[..2]);
+
+        let (height, _, work_a, work_b) =
+            <(U256, B256, U256, U256)>::abi_decode(&journal, true).unwrap();
+        assert_eq!(height, U256::from(1));
+        // Two headers at the genesis difficulty, 0x100010001 hashes each.
+        assert_eq!(work_a, U256::from(2 * 0x1_0001_0001u64));
+        assert_eq!(work_b, U256::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "branches do not start from the same header")]
+    fn rejects_unrelated_branches() {
+        compare_forks(&MAINNET_HEADERS[..1], &MAINNET_HEADERS[1..2]);
+    }
+
+    #[derive(Serialize)]
+    struct SparseInput {
+        network: Network,
+        heights: Vec<u64>,
+        headers: Vec<u8>,
+    }
+
+    fn verify_sparse(heights: &[u64], headers: &[&str]) -> Vec<u8> {
+        let input = SparseInput {
+            network: Network::Mainnet,
+            heights: heights.to_vec(),
+            headers: headers
+                .iter()
+                .flat_map(|h| hex::decode(h).unwrap())
+                .collect(),
+        };
+        let env = ExecutorEnv::builder()
+            .write(&input)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        default_executor()
+            .execute(env, super::BITCOIN_SPARSE_VERIFY_ELF)
+            .unwrap()
+            .journal
+            .bytes
+    }
+
+    #[test]
+    fn verifies_sparse_sample() {
+        let sample = [MAINNET_HEADERS[0], MAINNET_HEADERS[1], MAINNET_HEADERS[3]];
+        let journal = verify_sparse(&[0, 1, 3], &sample);
+
+        let (heights, hashes) =
+            <(Vec<U256>, Vec<B256>)>::abi_decode_params(&journal, true).unwrap();
+        assert_eq!(heights, vec![U256::from(0), U256::from(1), U256::from(3)]);
+        assert_eq!(hashes.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "header 1 does not extend header 0")]
+    fn checks_linkage_within_sparse_runs() {
+        // Headers 0 and 2 declared as consecutive.
+        verify_sparse(&[0, 1], &[MAINNET_HEADERS[0], MAINNET_HEADERS[2]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "header 0 does not meet its claimed target")]
+    fn checks_work_of_sparse_headers() {
+        let mut header = MAINNET_HEADERS[1].to_string();
+        header.replace_range(152..160, "00000000");
+        verify_sparse(&[1], &[&header]);
+    }
+
+    #[derive(Serialize)]
+    struct StreamInput {
+        network: Network,
+        start_height: u64,
+        anchor: RetargetAnchor,
+        chunks: u32,
+    }
+
+    /// Streams `headers` from genesis to the guest in chunks of at most `chunk_len` headers.
+    fn verify_stream(headers: &[u8], chunk_len: usize) -> Vec<u8> {
+        let chunks: Vec<Vec<u8>> = headers.chunks(chunk_len * 80).map(<[u8]>::to_vec).collect();
+        let input = StreamInput {
+            network: Network::Regtest,
+            start_height: 0,
+            anchor: RetargetAnchor {
+                bits: REGTEST_BITS,
+                window_start_time: u32::from_le_bytes(headers[68..72].try_into().unwrap()),
+            },
+            chunks: chunks.len() as u32,
+        };
+        let mut builder = ExecutorEnv::builder();
+        builder.write(&input).unwrap();
+        for chunk in &chunks {
+            builder.write(chunk).unwrap();
+        }
+
+        default_executor()
+            .execute(builder.build().unwrap(), super::BITCOIN_STREAM_VERIFY_ELF)
+            .unwrap()
+            .journal
+            .bytes
+    }
+
+    /// The fields of a stream journal, in order.
+    type StreamFields = (U256, B256, U256, B256, U256, u32, u32, u32, u32, U256);
+
+    #[test]
+    fn streamed_and_single_buffer_proving_agree() {
+        let headers = mine_regtest_headers(30);
+        let single = verify_stream(&headers, 30);
+        assert_eq!(verify_stream(&headers, 7), single);
+        assert_eq!(verify_stream(&headers, 1), single);
+
+        let mut input = mainnet_input(&[REGTEST_DIFFICULTY_HEADER]);
+        input.network = Network::Regtest;
+        input.height = 29;
+        input.anchor.window_start_time = 1_296_688_602;
+        input.headers = headers;
+        let (
+            height,
+            hash,
+            _,
+            median_time_past,
+            ..,
+            parent_hash,
+            _,
+            _,
+            end_bits,
+            end_time,
+            _,
+            _,
+            _,
+            _,
+        ) = JournalFields::abi_decode(&execute(&input), true).unwrap();
+
+        let (start, parent, streamed_height, tip, mtp, _, _, streamed_bits, streamed_time, work) =
+            StreamFields::abi_decode(&single, true).unwrap();
+        assert_eq!(start, U256::ZERO);
+        assert_eq!(
+            (streamed_height, tip, mtp),
+            (height, hash, median_time_past)
+        );
+        assert_eq!(parent, parent_hash);
+        assert_eq!((streamed_bits, streamed_time), (end_bits, end_time));
+        assert_eq!(work, U256::from(30 * 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk 1 does not extend chunk 0")]
+    fn checks_linkage_across_chunks() {
+        let mut headers = mine_regtest_headers(3);
+        headers.drain(80..160);
+        verify_stream(&headers, 1);
+    }
 
     #[test]
     fn proves_even_number() {
@@ -0,0 +1,687 @@
+//! Bitcoin header validation shared by the guests and the host.
+//!
+//! The guests run these checks inside the proof and panic on the returned error; the host runs
+//! the same checks before proving so a bad span fails fast with the same message.
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+use core::fmt;
+
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+
+/// Length of a serialized Bitcoin block header.
+pub const HEADER_LEN: usize = 80;
+
+/// Number of blocks between difficulty retargets.
+pub const RETARGET_INTERVAL: u64 = 2016;
+
+/// Time a retarget window is expected to take, two weeks in seconds.
+const TARGET_TIMESPAN: u64 = 14 * 24 * 60 * 60;
+
+/// Gap after which testnet and regtest allow a block at the pow_limit, twenty minutes.
+const MIN_DIFFICULTY_GAP: u32 = 20 * 60;
+
+/// The Bitcoin network the supplied headers belong to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl Network {
+    /// Byte identifying the network in a committed journal, its position in this enum.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// The network a committed journal identifies by `id`, if any.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Network::Mainnet),
+            1 => Some(Network::Testnet),
+            2 => Some(Network::Signet),
+            3 => Some(Network::Regtest),
+            _ => None,
+        }
+    }
+
+    /// Compact encoding of the easiest target a header on this network may claim.
+    pub fn pow_limit_bits(self) -> u32 {
+        match self {
+            Network::Mainnet | Network::Testnet => 0x1d00ffff,
+            Network::Signet => 0x1e0377ae,
+            Network::Regtest => 0x207fffff,
+        }
+    }
+
+    /// Whether the target is recomputed at every retarget boundary.
+    pub fn retargets(self) -> bool {
+        self != Network::Regtest
+    }
+
+    /// Whether a block more than twenty minutes after its parent may claim the pow_limit.
+    pub fn allows_min_difficulty(self) -> bool {
+        matches!(self, Network::Testnet | Network::Regtest)
+    }
+}
+
+/// The difficulty in force for the retarget window containing the span's first header.
+///
+/// The guest cannot derive it from a span that starts mid-window, so it is trusted as given and
+/// committed, letting a verifier check it against the proof of the preceding span.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetargetAnchor {
+    /// `nBits` of the first header of the window.
+    pub bits: u32,
+    /// Timestamp of the first header of the window, needed to recompute the target at the next
+    /// boundary.
+    pub window_start_time: u32,
+}
+
+/// A BIP9 signaling threshold the span must meet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionBits {
+    /// Version bits a header must all set to count as signaling.
+    pub mask: u32,
+    /// Fewest signaling headers the span must contain.
+    pub min_count: u32,
+}
+
+/// Why a span of headers is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The first header claims a target easier than the network allows.
+    PowLimit { bits: u32 },
+    /// A header's hash does not meet the target its `nBits` claims.
+    Work { index: usize },
+    /// A header's `prev_blockhash` is not the hash of the header before it.
+    Linkage { index: usize },
+    /// The span starts on a retarget boundary whose timestamp is not the anchor's.
+    AnchorTime,
+    /// A header claims an `nBits` other than the one in force at its height.
+    Difficulty {
+        index: usize,
+        claimed: u32,
+        expected: u32,
+    },
+    /// Fewer headers signal the required version bits than the threshold.
+    Signaling {
+        mask: u32,
+        count: u32,
+        min_count: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::PowLimit { bits } => write!(
+                f,
+                "first header's nBits {:#010x} exceeds the network's pow_limit",
+                bits
+            ),
+            Error::Work { index } => {
+                write!(f, "header {} does not meet its claimed target", index)
+            }
+            Error::Linkage { index } => {
+                write!(f, "header {} does not extend header {}", index, index - 1)
+            }
+            Error::AnchorTime => {
+                write!(
+                    f,
+                    "anchor time does not match the first header of the window"
+                )
+            }
+            Error::Difficulty {
+                index,
+                claimed,
+                expected,
+            } => write!(
+                f,
+                "header {} claims nBits {:#010x}, expected {:#010x}",
+                index, claimed, expected
+            ),
+            Error::Signaling {
+                mask,
+                count,
+                min_count,
+            } => write!(
+                f,
+                "{} headers signal version bits {:#010x}, {} required",
+                count, mask, min_count
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Returns the `time` field of a serialized header.
+pub fn timestamp(header: &[u8]) -> u32 {
+    u32::from_le_bytes(header[68..72].try_into().unwrap())
+}
+
+/// Returns the `nBits` field of a serialized header.
+pub fn header_bits(header: &[u8]) -> u32 {
+    u32::from_le_bytes(header[72..76].try_into().unwrap())
+}
+
+/// Returns the `version` field of a serialized header.
+pub fn header_version(header: &[u8]) -> u32 {
+    u32::from_le_bytes(header[0..4].try_into().unwrap())
+}
+
+/// Whether a header signals every bit in `mask` under BIP9, whose top three version bits must
+/// be `001`.
+pub fn signals(header: &[u8], mask: u32) -> bool {
+    let version = header_version(header);
+    version & 0xe000_0000 == 0x2000_0000 && version & mask == mask
+}
+
+/// Counts the headers in `raw` signaling `version_bits.mask` and checks the count meets its
+/// threshold.
+pub fn check_signaling(raw: &[u8], version_bits: VersionBits) -> Result<u32, Error> {
+    let count = raw
+        .chunks_exact(HEADER_LEN)
+        .filter(|header| signals(header, version_bits.mask))
+        .count() as u32;
+    if count < version_bits.min_count {
+        return Err(Error::Signaling {
+            mask: version_bits.mask,
+            count,
+            min_count: version_bits.min_count,
+        });
+    }
+    Ok(count)
+}
+
+/// Expands a compact `nBits` value into the full 256-bit target, as Bitcoin Core's `SetCompact`
+/// does. The sign bit is ignored, and mantissa bits shifted past 256 bits are dropped.
+pub fn target_from_bits(bits: u32) -> U256 {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = U256::from(bits & 0x007f_ffff);
+    if exponent <= 3 {
+        mantissa >> (8 * (3 - exponent))
+    } else {
+        mantissa << (8 * (exponent - 3))
+    }
+}
+
+/// Encodes a target in the compact `nBits` form, as Bitcoin Core's `GetCompact` does.
+pub fn bits_from_target(target: U256) -> u32 {
+    let mut size = (target.bit_len() as u32).div_ceil(8);
+    let mut compact = if size <= 3 {
+        (target.as_limbs()[0] << (8 * (3 - size))) as u32
+    } else {
+        (target >> (8 * (size - 3) as usize)).as_limbs()[0] as u32
+    };
+    // The mantissa's top bit is a sign bit, so push it into the next byte.
+    if compact & 0x0080_0000 != 0 {
+        compact >>= 8;
+        size += 1;
+    }
+    compact | size << 24
+}
+
+/// Returns the difficulty of `bits` relative to the genesis target `0x1d00ffff`, as Bitcoin
+/// Core's `GetDifficulty` reports it.
+pub fn compact_target_to_difficulty(bits: u32) -> f64 {
+    let mut shift = (bits >> 24) & 0xff;
+    let mut difficulty = f64::from(0x0000_ffff) / f64::from(bits & 0x00ff_ffff);
+    while shift < 29 {
+        difficulty *= 256.0;
+        shift += 1;
+    }
+    while shift > 29 {
+        difficulty /= 256.0;
+        shift -= 1;
+    }
+    difficulty
+}
+
+/// Returns the expected number of hashes needed to meet `bits`, as Bitcoin Core's
+/// `GetBlockProof` computes a header's work.
+pub fn header_work(bits: u32) -> U256 {
+    let target = target_from_bits(bits);
+    if target.is_zero() {
+        return U256::ZERO;
+    }
+    // 2^256 / (target + 1), computed without overflowing 256 bits.
+    !target / (target + U256::from(1)) + U256::from(1)
+}
+
+/// Sums the work of the headers in `raw`, or returns `None` if the sum overflows 256 bits.
+///
+/// No real chain comes close, but a span of forged headers at tiny targets could, and a wrapped
+/// sum would commit a misleading chainwork.
+pub fn chain_work(raw: &[u8]) -> Option<U256> {
+    raw.chunks_exact(HEADER_LEN)
+        .try_fold(U256::ZERO, |work, header| {
+            work.checked_add(header_work(header_bits(header)))
+        })
+}
+
+/// Computes the `nBits` of the first header of a window, as Bitcoin Core's
+/// `CalculateNextWorkRequired` does.
+pub fn next_bits(network: Network, last_bits: u32, window_start_time: u32, last_time: u32) -> u32 {
+    let timespan = (last_time as i64 - window_start_time as i64)
+        .clamp(TARGET_TIMESPAN as i64 / 4, TARGET_TIMESPAN as i64 * 4) as u64;
+    let target = target_from_bits(last_bits) * U256::from(timespan) / U256::from(TARGET_TIMESPAN);
+    bits_from_target(target.min(target_from_bits(network.pow_limit_bits())))
+}
+
+/// Checks that the first header does not claim a target easier than the network's pow_limit.
+///
+/// `validate_header_chain` only checks each header's work against its own `nBits`, so without
+/// this a forged chain could simply claim a trivial target.
+pub fn check_pow_limit(network: Network, header: &[u8]) -> Result<(), Error> {
+    let bits = header_bits(header);
+    // Negative targets and exponents that would overflow 256 bits are never valid.
+    let well_formed = bits & 0x0080_0000 == 0 && bits >> 24 <= 32;
+    if well_formed && target_from_bits(bits) <= target_from_bits(network.pow_limit_bits()) {
+        Ok(())
+    } else {
+        Err(Error::PowLimit { bits })
+    }
+}
+
+/// Checks that the header at `index`, whose hash is `hash` in header digest byte order, meets
+/// the target its own `nBits` claims.
+pub fn check_work(index: usize, header: &[u8], hash: [u8; 32]) -> Result<(), Error> {
+    if U256::from_le_bytes(hash) <= target_from_bits(header_bits(header)) {
+        Ok(())
+    } else {
+        Err(Error::Work { index })
+    }
+}
+
+/// Checks that each header's `prev_blockhash` is the hash of the header immediately before it.
+///
+/// `hashes` holds the hash of every header in `raw`, in order and in header digest byte order,
+/// so that a caller which needs them for other checks too only hashes each header once. A
+/// failure names the offending header so a gap or reordering in the buffer is easy to spot.
+pub fn check_linkage(raw: &[u8], hashes: &[[u8; 32]]) -> Result<(), Error> {
+    for (i, header) in raw.chunks_exact(HEADER_LEN).enumerate().skip(1) {
+        if header[4..36] != hashes[i - 1] {
+            return Err(Error::Linkage { index: i });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every header claims the difficulty in force at its height.
+///
+/// Starting from `anchor`, the `nBits` must stay constant within a window and match the
+/// retargeted value at each boundary. On testnet and regtest, a header more than twenty minutes
+/// after its parent must instead claim the pow_limit, and the header after it returns to the last
+/// target that was not a minimum-difficulty exception, as Bitcoin Core's `GetNextWorkRequired`
+/// walks back to find. A span cannot start on such a header, since its parent's timestamp is not
+/// known.
+///
+/// Returns the anchor for the header after the span, so that adjacent spans can be chained. If
+/// that header starts a new window, its `bits` are retargeted from the span's last header and its
+/// `window_start_time` is zero, since the window starts at that header's own timestamp.
+pub fn check_difficulty(
+    network: Network,
+    raw: &[u8],
+    start_height: u64,
+    anchor: RetargetAnchor,
+) -> Result<RetargetAnchor, Error> {
+    // The last target that was not a minimum-difficulty exception, which within a window is the
+    // target set at its boundary.
+    let mut bits = anchor.bits;
+    let mut window_start_time = anchor.window_start_time;
+    let mut parent: Option<&[u8]> = None;
+    for (i, header) in raw.chunks_exact(HEADER_LEN).enumerate() {
+        let height = start_height + i as u64;
+        let claimed = header_bits(header);
+        if height % RETARGET_INTERVAL == 0 {
+            match parent {
+                // Like Core, retarget from the last header's own `nBits`, even when it is a
+                // minimum-difficulty exception.
+                Some(parent) if network.retargets() => {
+                    bits = next_bits(
+                        network,
+                        header_bits(parent),
+                        window_start_time,
+                        timestamp(parent),
+                    );
+                }
+                Some(_) => {}
+                None if timestamp(header) != anchor.window_start_time => {
+                    return Err(Error::AnchorTime)
+                }
+                None => {}
+            }
+            window_start_time = timestamp(header);
+        }
+
+        let min_difficulty = network.allows_min_difficulty()
+            && height % RETARGET_INTERVAL != 0
+            && parent.is_some_and(|parent| {
+                timestamp(header) > timestamp(parent).saturating_add(MIN_DIFFICULTY_GAP)
+            });
+        let expected = if min_difficulty {
+            network.pow_limit_bits()
+        } else {
+            bits
+        };
+        if claimed != expected {
+            return Err(Error::Difficulty {
+                index: i,
+                claimed,
+                expected,
+            });
+        }
+        parent = Some(header);
+    }
+
+    let next_height = start_height + (raw.len() / HEADER_LEN) as u64;
+    if let Some(last) = parent.filter(|_| next_height % RETARGET_INTERVAL == 0) {
+        if network.retargets() {
+            bits = next_bits(
+                network,
+                header_bits(last),
+                window_start_time,
+                timestamp(last),
+            );
+        }
+        window_start_time = 0;
+    }
+    Ok(RetargetAnchor {
+        bits,
+        window_start_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+
+    use super::{
+        bits_from_target, chain_work, check_difficulty, check_linkage, check_pow_limit,
+        check_signaling, compact_target_to_difficulty, header_work, signals, target_from_bits,
+        Error, Network, RetargetAnchor, VersionBits, HEADER_LEN,
+    };
+
+    /// Builds a buffer of headers with only the timestamp and `nBits` set, which is all the
+    /// difficulty checks read.
+    fn headers(fields: &[(u32, u32)]) -> Vec<u8> {
+        fields
+            .iter()
+            .flat_map(|&(time, bits)| {
+                let mut header = [0; HEADER_LEN];
+                header[68..72].copy_from_slice(&time.to_le_bytes());
+                header[72..76].copy_from_slice(&bits.to_le_bytes());
+                header
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_core_compact_vectors() {
+        // From Bitcoin Core's arith_uint256 tests: (nBits, target, GetCompact of the target).
+        for (bits, target, compact) in [
+            (0x0000_0000, U256::ZERO, 0x0000_0000),
+            (0x0112_3456, U256::from(0x12), 0x0112_0000),
+            (0x0212_3456, U256::from(0x1234), 0x0212_3400),
+            (0x0312_3456, U256::from(0x12_3456), 0x0312_3456),
+            (0x0412_3456, U256::from(0x1234_5600), 0x0412_3456),
+            (0x0500_9234, U256::from(0x9234_0000u32), 0x0500_9234),
+            (0x2012_3456, U256::from(0x12_3456) << (8 * 29), 0x2012_3456),
+        ] {
+            assert_eq!(target_from_bits(bits), target, "{:#010x}", bits);
+            assert_eq!(bits_from_target(target), compact, "{:#010x}", bits);
+        }
+    }
+
+    #[test]
+    fn matches_core_difficulty_vectors() {
+        // From Bitcoin Core's blockchain tests.
+        for (bits, expected) in [
+            (0x1f111111, 0.000001),
+            (0x1ef88f6f, 0.000016),
+            (0x1df88f6f, 0.004023),
+            (0x1cf88f6f, 1.029916),
+            (0x1d00ffff, 1.0),
+            (0x12345678, 5.913134931067755e24),
+        ] {
+            let difficulty = compact_target_to_difficulty(bits);
+            assert!(
+                (difficulty - expected).abs() <= 0.00001 * expected.max(1.0),
+                "{:#010x}: {} != {}",
+                bits,
+                difficulty,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_pow_limits() {
+        for bits in [0x1d00ffff, 0x1e0377ae, 0x207fffff] {
+            assert_eq!(bits_from_target(target_from_bits(bits)), bits);
+        }
+    }
+
+    #[test]
+    fn round_trips_network_ids() {
+        for network in [
+            Network::Mainnet,
+            Network::Testnet,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            assert_eq!(Network::from_id(network.id()), Some(network));
+        }
+        assert_eq!(Network::from_id(4), None);
+    }
+
+    #[test]
+    fn detects_bip9_signaling() {
+        let header = |version: u32| {
+            let mut header = [0; HEADER_LEN];
+            header[0..4].copy_from_slice(&version.to_le_bytes());
+            header
+        };
+
+        // Taproot's bit 2.
+        assert!(signals(&header(0x2000_0004), 1 << 2));
+        assert!(!signals(&header(0x2000_0000), 1 << 2));
+        // Without the BIP9 top bits, no bit counts as signaling.
+        assert!(!signals(&header(0x0000_0004), 1 << 2));
+        assert!(!signals(&header(0x6000_0004), 1 << 2));
+
+        let raw = [header(0x2000_0004), header(0x2000_0001)].concat();
+        let version_bits = |min_count| VersionBits {
+            mask: 1 << 2,
+            min_count,
+        };
+        assert_eq!(check_signaling(&raw, version_bits(1)), Ok(1));
+        assert_eq!(
+            check_signaling(&raw, version_bits(2)),
+            Err(Error::Signaling {
+                mask: 1 << 2,
+                count: 1,
+                min_count: 2
+            })
+        );
+    }
+
+    #[test]
+    fn checks_linkage_against_given_hashes() {
+        let mut raw = [[0; HEADER_LEN]; 3];
+        raw[1][4..36].copy_from_slice(&[0xaa; 32]);
+        raw[2][4..36].copy_from_slice(&[0xbb; 32]);
+        let raw = raw.concat();
+
+        assert_eq!(
+            check_linkage(&raw, &[[0xaa; 32], [0xbb; 32], [0; 32]]),
+            Ok(())
+        );
+        assert_eq!(
+            check_linkage(&raw, &[[0xaa; 32], [0xcc; 32], [0; 32]]),
+            Err(Error::Linkage { index: 2 })
+        );
+    }
+
+    #[test]
+    fn computes_genesis_work() {
+        assert_eq!(header_work(0x1d00ffff), U256::from(0x1_0001_0001u64));
+    }
+
+    #[test]
+    fn detects_chain_work_overflow() {
+        assert_eq!(
+            chain_work(&headers(&[(0, 0x1d00ffff), (0, 0x1d00ffff)])),
+            Some(U256::from(0x2_0002_0002u64))
+        );
+
+        // A target of 1 takes 2^255 hashes, so two such headers sum to exactly 2^256.
+        assert_eq!(header_work(0x0101_0000), U256::from(1) << 255);
+        assert_eq!(
+            chain_work(&headers(&[(0, 0x0101_0000)])),
+            Some(U256::from(1) << 255)
+        );
+        assert_eq!(
+            chain_work(&headers(&[(0, 0x0101_0000), (0, 0x0101_0000)])),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_bits_above_pow_limit() {
+        let mut header = [0; 80];
+        header[72..76].copy_from_slice(&0x207fffffu32.to_le_bytes());
+
+        assert!(check_pow_limit(Network::Regtest, &header).is_ok());
+        assert_eq!(
+            check_pow_limit(Network::Mainnet, &header),
+            Err(Error::PowLimit { bits: 0x207fffff })
+        );
+    }
+
+    #[test]
+    fn allows_testnet_min_difficulty_after_twenty_minutes() {
+        // A testnet3 stretch mid-window at 0x1a01aa3d: a block 21 minutes after its parent drops
+        // to the pow_limit, and the next block, found a minute later, returns to the window's
+        // target rather than staying at the exception's.
+        let anchor = RetargetAnchor {
+            bits: 0x1a01aa3d,
+            window_start_time: 1_400_000_000,
+        };
+        let span = headers(&[
+            (1_400_100_000, 0x1a01aa3d),
+            (1_400_101_260, 0x1d00ffff),
+            (1_400_101_320, 0x1a01aa3d),
+            (1_400_102_520, 0x1a01aa3d),
+        ]);
+        assert_eq!(
+            check_difficulty(Network::Testnet, &span, 10_000, anchor),
+            Ok(anchor)
+        );
+
+        // Mainnet has no such exception.
+        assert_eq!(
+            check_difficulty(Network::Mainnet, &span, 10_000, anchor),
+            Err(Error::Difficulty {
+                index: 1,
+                claimed: 0x1d00ffff,
+                expected: 0x1a01aa3d,
+            })
+        );
+    }
+
+    #[test]
+    fn chains_anchor_across_retarget_boundary() {
+        // The window took half the target timespan, so the next one halves the target.
+        let anchor = RetargetAnchor {
+            bits: 0x1d00ffff,
+            window_start_time: 1_400_000_000,
+        };
+        let before = headers(&[(1_400_604_000, 0x1d00ffff), (1_400_604_800, 0x1d00ffff)]);
+        let end_anchor = check_difficulty(Network::Mainnet, &before, 2014, anchor).unwrap();
+        assert_eq!(
+            end_anchor,
+            RetargetAnchor {
+                bits: 0x1c7fff80,
+                window_start_time: 0,
+            }
+        );
+
+        // The next span is anchored at the committed bits and its own first timestamp.
+        let after = headers(&[(1_400_605_400, 0x1c7fff80), (1_400_606_000, 0x1c7fff80)]);
+        let next_anchor = RetargetAnchor {
+            bits: end_anchor.bits,
+            window_start_time: 1_400_605_400,
+        };
+        assert_eq!(
+            check_difficulty(Network::Mainnet, &after, 2016, next_anchor),
+            Ok(next_anchor)
+        );
+
+        // Within a window, the end anchor is the span's own.
+        assert_eq!(
+            check_difficulty(Network::Mainnet, &before[..HEADER_LEN], 2014, anchor),
+            Ok(anchor)
+        );
+    }
+
+    #[test]
+    fn rejects_testnet_min_difficulty_within_twenty_minutes() {
+        let anchor = RetargetAnchor {
+            bits: 0x1a01aa3d,
+            window_start_time: 1_400_000_000,
+        };
+        // Exactly twenty minutes is not enough.
+        let early = headers(&[(1_400_100_000, 0x1a01aa3d), (1_400_101_200, 0x1d00ffff)]);
+        assert_eq!(
+            check_difficulty(Network::Testnet, &early, 10_000, anchor),
+            Err(Error::Difficulty {
+                index: 1,
+                claimed: 0x1d00ffff,
+                expected: 0x1a01aa3d,
+            })
+        );
+
+        // After the exception, the next block must not keep the pow_limit.
+        let sticky = headers(&[
+            (1_400_100_000, 0x1a01aa3d),
+            (1_400_101_260, 0x1d00ffff),
+            (1_400_101_320, 0x1d00ffff),
+        ]);
+        assert_eq!(
+            check_difficulty(Network::Testnet, &sticky, 10_000, anchor),
+            Err(Error::Difficulty {
+                index: 2,
+                claimed: 0x1d00ffff,
+                expected: 0x1a01aa3d,
+            })
+        );
+    }
+
+    #[test]
+    fn requires_testnet_min_difficulty_after_twenty_minutes() {
+        // Core requires the pow_limit once the gap has passed, so the window's target is wrong.
+        let anchor = RetargetAnchor {
+            bits: 0x1a01aa3d,
+            window_start_time: 1_400_000_000,
+        };
+        let span = headers(&[(1_400_100_000, 0x1a01aa3d), (1_400_101_260, 0x1a01aa3d)]);
+        assert_eq!(
+            check_difficulty(Network::Testnet, &span, 10_000, anchor),
+            Err(Error::Difficulty {
+                index: 1,
+                claimed: 0x1a01aa3d,
+                expected: 0x1d00ffff,
+            })
+        );
+    }
+}